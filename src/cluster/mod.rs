@@ -1,4 +1,9 @@
+mod segmenter;
+
+pub use segmenter::*;
+
 use super::*;
+use crate::text::WidthOptions;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Formatter;
@@ -51,6 +56,23 @@ impl<'a> Cluster<'a> {
     pub fn width(&self) -> u8 {
         self.width
     }
+
+    /// Returns the display width at `column`, with `options`.
+    ///
+    /// Tabs stretch to the next tab stop, and clusters are at most 2 columns
+    /// wide.
+    pub fn width_with(&self, options: &WidthOptions, column: usize) -> usize {
+        let width = match self.str {
+            "\t" => return options.tab_width - column % options.tab_width.max(1),
+            str if options.ambiguous_wide => utils::width_cjk(str),
+            _ => self.width as usize,
+        };
+
+        match width {
+            0 => options.zero_width,
+            width => width.min(2),
+        }
+    }
 }
 
 impl<'a> Cluster<'a> {
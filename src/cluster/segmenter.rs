@@ -0,0 +1,223 @@
+use super::*;
+use unicode_segmentation::GraphemeIncomplete;
+
+/// Segments `&str` chunks in grapheme [`Cluster`]s.
+///
+/// Clusters can straddle chunks (e.g. `"e"` then `"\u{301}"`): the start of
+/// a cluster is kept until the chunk completing it, so that all clusters are
+/// contiguous. Other clusters borrow from their chunk.
+///
+/// Clusters come with their byte offset in the stream of chunks.
+///
+/// # Usage
+///
+/// ```
+/// # use grappe::cluster::Segmenter;
+/// let mut segmenter = Segmenter::default();
+///
+/// // Feed first chunk (an `'e'` waiting for a potential combining mark)
+/// let mut clusters = segmenter.segment("le");
+/// assert!(clusters
+///     .next()
+///     .is_some_and(|(i, c)| i == 0 && c.as_str() == "l"));
+/// assert!(clusters.next().is_none());
+///
+/// // Feed second chunk, completing the `'e'` with an acute accent
+/// let mut clusters = segmenter.segment("\u{301}té");
+/// assert!(clusters
+///     .next()
+///     .is_some_and(|(i, c)| i == 1 && c.as_str() == "e\u{301}"));
+/// assert!(clusters
+///     .next()
+///     .is_some_and(|(i, c)| i == 4 && c.as_str() == "t"));
+/// assert!(clusters.next().is_none());
+///
+/// // After feeding all chunks
+/// let last = segmenter.done();
+/// assert!(last.is_some_and(|(i, c)| i == 5 && c.as_str() == "é"));
+/// ```
+///
+/// Byte chunks (e.g. from a reader) can be validated with
+/// [`Validator`](crate::utils::Validator) first, and split at eols with
+/// [`Splitter`](crate::eol::Splitter) (eols always end clusters).
+#[derive(Clone, Default, Debug)]
+pub struct Segmenter {
+    /// Offset of `pending` in the stream.
+    start:   usize,
+    /// Start of the cluster in progress, from previous chunks.
+    pending: String,
+    /// Cluster straddling chunks, completed by the current chunk.
+    joint:   String,
+}
+
+impl Segmenter {
+    /// Feeds chunk to segment, returning the iterator.
+    ///
+    /// The returned iterator must be exhausted
+    /// before calling this function again.
+    pub fn segment<'a>(&'a mut self, str: &'a str) -> Segments<'a> {
+        let Self {
+            start,
+            pending,
+            joint,
+        } = self;
+        let chunk = *start + pending.len();
+        let end = if pending.is_empty() {
+            None
+        } else {
+            complete(joint, pending, str)
+        };
+
+        // The stream before the chunk (from a boundary), for the cursor
+        let context = (*start, chunk - *start);
+        let at = match end {
+            Some(end) => {
+                pending.clear();
+                *start + end
+            }
+            None if !pending.is_empty() => {
+                pending.push_str(str);
+                chunk + str.len()
+            }
+            None => chunk,
+        };
+        let joint: &'a String = joint;
+
+        Segments {
+            cursor: GraphemeCursor::new(at, usize::MAX, true),
+            start,
+            pending,
+            context: match end {
+                Some(_) => (context.0, &joint[..context.1]),
+                None => (chunk, ""),
+            },
+            joint: end.map(|_| joint.as_str()),
+            str,
+            chunk,
+            at,
+        }
+    }
+
+    /// Retrieves the very last cluster of the stream, with its offset.
+    ///
+    /// Call after feeding all chunks and exhausting those iterators.
+    /// State is reset to allow reuse for another chunk stream.
+    pub fn done(&mut self) -> Option<(usize, Cluster<'_>)> {
+        let start = self.start;
+
+        self.start = 0;
+        self.joint = std::mem::take(&mut self.pending);
+
+        (!self.joint.is_empty()).then(|| (start, cluster(&self.joint)))
+    }
+}
+
+/// Iterator of [`Cluster`]s (with their offset in the stream), returned from
+/// [`Segmenter::segment`].
+#[derive(Debug)]
+pub struct Segments<'a> {
+    cursor:  GraphemeCursor,
+    start:   &'a mut usize,
+    pending: &'a mut String,
+    /// Offset of the stream before the chunk (from a boundary), and that
+    /// stream.
+    context: (usize, &'a str),
+    joint:   Option<&'a str>,
+    str:     &'a str,
+    /// Offset of `str` in the stream.
+    chunk:   usize,
+    /// Offset of the next cluster in the stream.
+    at:      usize,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = (usize, Cluster<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(joint) = self.joint.take() {
+            return Some((self.context.0, cluster(joint)));
+        }
+
+        let end = self.chunk + self.str.len();
+        if self.at == end {
+            return None;
+        }
+
+        let start = self.at;
+
+        match next_boundary(&mut self.cursor, self.str, self.chunk, self.context) {
+            Some(boundary) => {
+                self.at = boundary;
+                Some((
+                    start,
+                    cluster(&self.str[start - self.chunk..boundary - self.chunk]),
+                ))
+            }
+            None => {
+                // Keep the rest for the next chunk
+                self.at = end;
+                *self.start = start;
+                self.pending.push_str(&self.str[start - self.chunk..]);
+                None
+            }
+        }
+    }
+}
+
+/// Completes the cluster starting `pending` with `str` in `joint`, returning
+/// its end (in `joint`), or `None` if `str` ends first.
+///
+/// `str` is copied in growing pieces, until the end of the cluster.
+fn complete(joint: &mut String, pending: &str, str: &str) -> Option<usize> {
+    let mut cursor = GraphemeCursor::new(0, usize::MAX, true);
+    let mut copied = 0;
+
+    joint.clear();
+    joint.push_str(pending);
+
+    while copied < str.len() {
+        let mut end = (2 * copied + 16).min(str.len());
+        while !str.is_char_boundary(end) {
+            end += 1;
+        }
+
+        joint.push_str(&str[copied..end]);
+        copied = end;
+
+        if let Some(end) = next_boundary(&mut cursor, joint, 0, (0, "")) {
+            joint.truncate(end);
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+/// Returns the cluster `str`.
+fn cluster(str: &str) -> Cluster<'_> {
+    Cluster::from_raw(str, utils::width(str) as u8)
+}
+
+/// Returns the next boundary (an offset in the stream) of `cursor` in `str`
+/// (at offset `chunk`), or `None` if `str` ends first, with `context` (at its
+/// offset) before `str`.
+fn next_boundary(
+    cursor: &mut GraphemeCursor,
+    str: &str,
+    chunk: usize,
+    (start, context): (usize, &str),
+) -> Option<usize> {
+    loop {
+        match cursor.next_boundary(str, chunk) {
+            Ok(boundary) => return boundary,
+            Err(GraphemeIncomplete::NextChunk) => return None,
+            // `start` is a boundary: what precedes does not matter, as if a
+            // control char
+            Err(GraphemeIncomplete::PreContext(end)) if end <= start =>
+                cursor.provide_context("\0", end - 1),
+            Err(GraphemeIncomplete::PreContext(end)) =>
+                cursor.provide_context(&context[..end - start], start),
+            Err(err) => unreachable!("{:?}", err),
+        }
+    }
+}
@@ -25,3 +25,67 @@ fn at_index() {
         assert!(cluster == None);
     }
 }
+
+#[test]
+fn segmenter() {
+    let strs = [
+        "e\u{301}\u{302}a\r\n\r\n",
+        "\u{1F1EB}\u{1F1F7}\u{1F1EE}\u{1F1F9}\u{1F1EA}",
+        "\u{1F469}\u{200D}\u{1F52C}\u{1F469}\u{1F3FD}\u{200D}\u{2764}\u{FE0F}",
+        "\u{915}\u{94D}\u{937}\u{93F} \u{1100}\u{1161}\u{11A8}",
+    ];
+    let mut segmenter = Segmenter::default();
+
+    for str in strs.iter().chain(FILES) {
+        let expected = str.grapheme_indices(true).collect::<Vec<_>>();
+
+        for size in 1..=8 {
+            let mut clusters = Vec::new();
+            let mut start = 0;
+
+            while start < str.len() {
+                let mut end = (start + size).min(str.len());
+                while !str.is_char_boundary(end) {
+                    end += 1;
+                }
+
+                for (i, cluster) in segmenter.segment(&str[start..end]) {
+                    clusters.push((i, cluster.as_str().to_owned()));
+                }
+                start = end;
+            }
+            if let Some((i, cluster)) = segmenter.done() {
+                clusters.push((i, cluster.as_str().to_owned()));
+            }
+
+            assert!(clusters.len() == expected.len());
+            for ((i, cluster), &(j, expected)) in clusters.iter().zip(&expected) {
+                assert!(*i == j);
+                assert!(cluster == expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn width_with() {
+    let options = WidthOptions {
+        tab_width:      4,
+        ambiguous_wide: true,
+        zero_width:     1,
+    };
+
+    for (str, default, with) in [
+        ("a", 1, 1),
+        ("\t", 5, 3),
+        ("°", 1, 2),
+        ("\u{200B}", 0, 1),
+        ("e\u{301}", 1, 1),
+        ("\u{1F469}\u{200D}\u{1F52C}", 2, 2),
+    ] {
+        let cluster = Cluster::from_raw(str, utils::width(str) as u8);
+
+        assert!(cluster.width_with(&WidthOptions::default(), 3) == default);
+        assert!(cluster.width_with(&options, 1) == with);
+    }
+}
@@ -1,5 +1,7 @@
+mod set;
 mod splitter;
 
+pub use set::*;
 pub use splitter::*;
 
 use std::fmt::Debug;
@@ -40,7 +42,7 @@ pub const CRLF: [u8; 2] = [CR, LF];
 // ========================================================================== //
 
 /// Line endings Unicode sequences.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum Eol {
     /// Line Feed (`'\u{000A}'` aka `'\n'`).
     LF,
@@ -61,6 +63,18 @@ pub enum Eol {
 }
 
 impl Eol {
+    /// All `Eol`s.
+    pub const ALL: [Self; 8] = [
+        Self::LF,
+        Self::VT,
+        Self::FF,
+        Self::CR,
+        Self::NEL,
+        Self::LS,
+        Self::PS,
+        Self::CRLF,
+    ];
+
     /// Returns the underlying `&[u8]`.
     pub fn as_bytes(&self) -> &'static [u8] {
         match self {
@@ -95,6 +109,8 @@ impl Eol {
 
     /// Spits `str` at first `Eol`, if any.
     ///
+    /// All eols are recognized, see [`EolSet::split`] to choose.
+    ///
     /// # Example
     ///
     /// ```
@@ -104,64 +120,14 @@ impl Eol {
     /// assert!(Eol::split("Goodbye\r\nWindows\r\n") == ("Goodbye", Some((Eol::CRLF, "Windows\r\n"))));
     /// ```
     pub fn split(str: &str) -> (&str, Option<(Self, &str)>) {
-        macro_rules! ret {
-            ($str:ident, $i:ident, $len:literal, $eol:ident) => {
-                return (&$str[..$i], Some((Self::$eol, &$str[$i + $len..])))
-            };
-        }
-
-        let bytes = str.as_bytes();
-
-        for (i, &byte) in bytes.iter().enumerate() {
-            let is = |byte, len| bytes.get(i + len) == Some(&byte);
-
-            match byte {
-                LF => ret!(str, i, 1, LF),
-                CR if is(LF, 1) => ret!(str, i, 2, CRLF),
-                CR => ret!(str, i, 1, CR),
-                VT => ret!(str, i, 1, VT),
-                FF => ret!(str, i, 1, FF),
-                NEL0 if is(NEL1, 1) => ret!(str, i, 2, NEL),
-                S0 if is(S1, 1) =>
-                    if is(LS2, 2) {
-                        ret!(str, i, 3, LS)
-                    } else if is(PS2, 2) {
-                        ret!(str, i, 3, PS)
-                    },
-                _ => {}
-            }
-        }
-
-        (str, None)
+        EolSet::ALL.split(str)
     }
 
+    /// Strips the `Eol` starting `str`, if any.
+    ///
+    /// All eols are recognized, see [`EolSet::starts_with`] to choose.
     pub fn starts_with(str: &str) -> (Option<Self>, &str) {
-        macro_rules! ret {
-            ($str:ident, $i:ident, $len:literal, $eol:ident) => {
-                return (Some(Self::$eol), &$str[$len..])
-            };
-        }
-
-        let bytes = str.as_bytes();
-        let is = |byte, len| bytes.get(len) == Some(&byte);
-
-        match bytes.get(0) {
-            Some(&LF) => ret!(str, i, 1, LF),
-            Some(&CR) if is(LF, 1) => ret!(str, i, 2, CRLF),
-            Some(&CR) => ret!(str, i, 1, CR),
-            Some(&VT) => ret!(str, i, 1, VT),
-            Some(&FF) => ret!(str, i, 1, FF),
-            Some(&NEL0) if is(NEL1, 1) => ret!(str, i, 2, NEL),
-            Some(&S0) if is(S1, 1) =>
-                if is(LS2, 2) {
-                    ret!(str, i, 3, LS)
-                } else if is(PS2, 2) {
-                    ret!(str, i, 3, PS)
-                },
-            _ => {}
-        }
-
-        (None, str)
+        EolSet::ALL.starts_with(str)
     }
 }
 
@@ -0,0 +1,159 @@
+use super::*;
+use std::iter::FromIterator;
+
+/// A set of [`Eol`]s to recognize as line endings.
+///
+/// Unrecognized eols are plain content. Without [`Eol::CRLF`], `"\r\n"` is
+/// [`Eol::CR`] then [`Eol::LF`] (if recognized), and without [`Eol::CR`], a
+/// `'\r'` not followed by `'\n'` is content.
+///
+/// Defaults to [`EolSet::ALL`].
+///
+/// # Example
+///
+/// ```
+/// # use grappe::eol::EolSet;
+/// # use grappe::Eol;
+/// let eols = EolSet::LF_CRLF;
+///
+/// assert!(eols.split("a\x0Cb\r\nc") == ("a\x0Cb", Some((Eol::CRLF, "c"))));
+/// assert!(eols.split("a\rb") == ("a\rb", None));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct EolSet {
+    bits: u8,
+}
+
+impl EolSet {
+    /// All eols.
+    pub const ALL: Self = Self::new(&Eol::ALL);
+    /// No eols.
+    pub const EMPTY: Self = Self::new(&[]);
+    /// `'\n'` and `"\r\n"`, e.g. for source code.
+    pub const LF_CRLF: Self = Self::new(&[Eol::LF, Eol::CRLF]);
+
+    /// Returns a set of `eols`.
+    pub const fn new(eols: &[Eol]) -> Self {
+        let mut set = Self { bits: 0 };
+        let mut i = 0;
+
+        while i < eols.len() {
+            set = set.with(eols[i]);
+            i += 1;
+        }
+
+        set
+    }
+
+    /// Returns this set with `eol`.
+    pub const fn with(self, eol: Eol) -> Self {
+        Self {
+            bits: self.bits | (1 << eol as u8),
+        }
+    }
+
+    /// Returns this set without `eol`.
+    pub const fn without(self, eol: Eol) -> Self {
+        Self {
+            bits: self.bits & !(1 << eol as u8),
+        }
+    }
+
+    /// Returns `true` if `eol` is in this set.
+    pub const fn contains(&self, eol: Eol) -> bool {
+        self.bits & (1 << eol as u8) != 0
+    }
+
+    /// Returns `true` if this set has no eols.
+    pub const fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Adds `eol`.
+    pub fn insert(&mut self, eol: Eol) {
+        *self = self.with(eol);
+    }
+
+    /// Removes `eol`.
+    pub fn remove(&mut self, eol: Eol) {
+        *self = self.without(eol);
+    }
+
+    /// Returns an iterator over the eols (in [`Eol::ALL`] order).
+    pub fn iter(&self) -> impl Iterator<Item = Eol> {
+        let set = *self;
+        Eol::ALL.into_iter().filter(move |&eol| set.contains(eol))
+    }
+
+    /// Splits `str` at the first eol of this set, if any (see [`Eol::split`]).
+    pub fn split<'a>(&self, str: &'a str) -> (&'a str, Option<(Eol, &'a str)>) {
+        self.split_within(str, str.len())
+    }
+
+    /// Splits `str` at the first eol of this set starting at or before byte
+    /// `max`, if any.
+    ///
+    /// Only looks at `max` bytes (plus the eol), so that long lines can be
+    /// consumed in bounded steps.
+    pub(crate) fn split_within<'a>(
+        &self,
+        str: &'a str,
+        max: usize,
+    ) -> (&'a str, Option<(Eol, &'a str)>) {
+        let bytes = str.as_bytes();
+
+        for i in 0..bytes.len().min(max.saturating_add(1)) {
+            if let Some(eol) = self.eol_at(&bytes[i..]) {
+                return (&str[..i], Some((eol, &str[i + eol.as_bytes().len()..])));
+            }
+        }
+
+        (str, None)
+    }
+
+    /// Strips the eol of this set starting `str`, if any (see
+    /// [`Eol::starts_with`]).
+    pub fn starts_with<'a>(&self, str: &'a str) -> (Option<Eol>, &'a str) {
+        match self.eol_at(str.as_bytes()) {
+            Some(eol) => (Some(eol), &str[eol.as_bytes().len()..]),
+            None => (None, str),
+        }
+    }
+
+    /// Returns the eol of this set starting `bytes`, if any.
+    fn eol_at(&self, bytes: &[u8]) -> Option<Eol> {
+        let is = |byte, i| bytes.get(i) == Some(&byte);
+
+        let eol = match *bytes.first()? {
+            LF => Eol::LF,
+            CR if is(LF, 1) && self.contains(Eol::CRLF) => Eol::CRLF,
+            CR => Eol::CR,
+            VT => Eol::VT,
+            FF => Eol::FF,
+            NEL0 if is(NEL1, 1) => Eol::NEL,
+            S0 if is(S1, 1) && is(LS2, 2) => Eol::LS,
+            S0 if is(S1, 1) && is(PS2, 2) => Eol::PS,
+            _ => return None,
+        };
+
+        Some(eol).filter(|&eol| self.contains(eol))
+    }
+}
+
+impl Default for EolSet {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl From<Eol> for EolSet {
+    fn from(eol: Eol) -> Self {
+        Self::EMPTY.with(eol)
+    }
+}
+
+impl FromIterator<Eol> for EolSet {
+    fn from_iter<I: IntoIterator<Item = Eol>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::EMPTY, Self::with)
+    }
+}
@@ -31,7 +31,8 @@ enum State {
     S1,
 }
 
-/// Splits byte chunks at end-of-line sequences.
+/// Splits byte chunks at end-of-line sequences (of an [`EolSet`], all by
+/// default).
 ///
 /// Care is taken to not split further than EOLs or chunk end,
 /// to maintain eventual UTF-8 validity.
@@ -67,9 +68,15 @@ enum State {
 #[derive(Copy, Clone, Default, Debug)]
 pub struct Splitter {
     state: Option<State>,
+    eols:  EolSet,
 }
 
 impl Splitter {
+    /// Creates a new `Splitter`, recognizing only `eols`.
+    pub fn new(eols: EolSet) -> Self {
+        Self { state: None, eols }
+    }
+
     /// Feeds chunk to split, returning the iterator.
     ///
     /// The returned iterator must be exhausted
@@ -77,6 +84,7 @@ impl Splitter {
     pub fn split<'a>(&'a mut self, bytes: &'a [u8]) -> Splits<'a> {
         Splits {
             state: &mut self.state,
+            eols: self.eols,
             bytes,
         }
     }
@@ -91,7 +99,8 @@ impl Splitter {
         self.state = None;
 
         Some(match state {
-            State::CR => Split::Eol(Eol::CR),
+            State::CR if self.eols.contains(Eol::CR) => Split::Eol(Eol::CR),
+            State::CR => Split::Bytes(&[CR]),
             State::NEL0 => Split::Bytes(&[NEL0]),
             State::S0 => Split::Bytes(&[S0]),
             State::S1 => Split::Bytes(&[S0, S1]),
@@ -104,6 +113,7 @@ impl Splitter {
 #[derive(Debug)]
 pub struct Splits<'a> {
     state: &'a mut Option<State>,
+    eols:  EolSet,
     bytes: &'a [u8],
 }
 
@@ -158,6 +168,12 @@ impl<'a> Splits<'a> {
         }
 
         let bytes = self.bytes;
+        let has = |eol| self.eols.contains(eol);
+        let (cr, nel, s) = (
+            has(Eol::CR) || has(Eol::CRLF),
+            has(Eol::NEL),
+            has(Eol::LS) || has(Eol::PS),
+        );
 
         if bytes.is_empty() {
             return None;
@@ -165,21 +181,21 @@ impl<'a> Splits<'a> {
 
         for (i, &byte) in bytes.iter().enumerate() {
             match byte {
-                LF => ret!(self, LF, i, 1),
-                CR => ret!(self, CR, i, 1),
-                VT => ret!(self, VT, i, 1),
-                FF => ret!(self, FF, i, 1),
-                NEL0 => match bytes.get(i + 1) {
+                LF if has(Eol::LF) => ret!(self, LF, i, 1),
+                CR if cr => ret!(self, CR, i, 1),
+                VT if has(Eol::VT) => ret!(self, VT, i, 1),
+                FF if has(Eol::FF) => ret!(self, FF, i, 1),
+                NEL0 if nel => match bytes.get(i + 1) {
                     None => ret!(self, NEL0, i, 1),
                     Some(&NEL1) => ret!(self, NEL, i, 2),
                     _ => {}
                 },
-                S0 => match bytes.get(i + 1) {
+                S0 if s => match bytes.get(i + 1) {
                     None => ret!(self, S0, i, 1),
                     Some(&S1) => match bytes.get(i + 2) {
                         None => ret!(self, S1, i, 2),
-                        Some(&LS2) => ret!(self, LS, i, 3),
-                        Some(&PS2) => ret!(self, PS, i, 3),
+                        Some(&LS2) if has(Eol::LS) => ret!(self, LS, i, 3),
+                        Some(&PS2) if has(Eol::PS) => ret!(self, PS, i, 3),
                         _ => {}
                     },
                     _ => {}
@@ -194,8 +210,9 @@ impl<'a> Splits<'a> {
 
     fn cr(&mut self) -> Option<Split<'a>> {
         match self.bytes.get(0) {
-            Some(&LF) => self.skip(1).eol(Eol::CRLF),
-            Some(_) => self.eol(Eol::CR),
+            Some(&LF) if self.eols.contains(Eol::CRLF) => self.skip(1).eol(Eol::CRLF),
+            Some(_) if self.eols.contains(Eol::CR) => self.eol(Eol::CR),
+            Some(_) => self.bytes(&[CR]),
             _ => None,
         }
     }
@@ -218,8 +235,8 @@ impl<'a> Splits<'a> {
 
     fn s1(&mut self) -> Option<Split<'a>> {
         match self.bytes.get(0) {
-            Some(&LS2) => self.skip(1).eol(Eol::LS),
-            Some(&PS2) => self.skip(1).eol(Eol::PS),
+            Some(&LS2) if self.eols.contains(Eol::LS) => self.skip(1).eol(Eol::LS),
+            Some(&PS2) if self.eols.contains(Eol::PS) => self.skip(1).eol(Eol::PS),
             Some(_) => self.bytes(&[S0, S1]),
             _ => None,
         }
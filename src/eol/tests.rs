@@ -186,3 +186,73 @@ fn test_splits(splits: &[Split]) {
         assert!(expected_eols == actual_eols);
     }
 }
+
+#[test]
+fn sets() {
+    fn push(splits: &mut (Vec<u8>, Vec<Eol>), split: Split) {
+        splits.0.extend_from_slice(split.as_bytes());
+
+        if let Split::Eol(eol) = split {
+            splits.1.push(eol);
+        }
+    }
+
+    let sets = [
+        EolSet::ALL,
+        EolSet::EMPTY,
+        EolSet::LF_CRLF,
+        EolSet::from(Eol::CR),
+        EolSet::from(Eol::CRLF),
+        EolSet::new(&[Eol::LF, Eol::CR]),
+        EolSet::new(&[Eol::NEL, Eol::LS, Eol::PS]),
+        EolSet::ALL.without(Eol::FF),
+    ];
+    let strs = [
+        "a\r\nb\rc\nd\x0Be\x0Cf\u{85}g\u{2028}h\u{2029}\r",
+        "\r\r\n\n\r\u{2028}\u{2029}→开‼§\u{85}",
+    ];
+
+    assert!(EolSet::default() == EolSet::ALL);
+    assert!(EolSet::ALL.iter().eq(Eol::ALL));
+    assert!(EolSet::EMPTY.is_empty());
+    assert!(EolSet::LF_CRLF.iter().collect::<EolSet>() == EolSet::LF_CRLF);
+    assert!(!EolSet::LF_CRLF.contains(Eol::CR));
+
+    for eols in sets {
+        for str in strs.iter().chain(FILES) {
+            // Expected splits, from `EolSet::split`
+            let mut expected = (Vec::<u8>::new(), Vec::<Eol>::new());
+            let mut rest = *str;
+            loop {
+                let (bytes, eol) = eols.split(rest);
+                expected.0.extend_from_slice(bytes.as_bytes());
+
+                match eol {
+                    Some((eol, tail)) => {
+                        assert!(eols.contains(eol));
+                        assert!(eols.starts_with(&rest[bytes.len()..]) == (Some(eol), tail));
+                        expected.0.extend_from_slice(eol.as_bytes());
+                        expected.1.push(eol);
+                        rest = tail;
+                    }
+                    None => break,
+                }
+            }
+
+            for size in [1, 2, 3, 5, str.len().max(1)] {
+                let mut splitter = Splitter::new(eols);
+                let mut actual = (Vec::<u8>::new(), Vec::<Eol>::new());
+                for chunk in str.as_bytes().chunks(size) {
+                    splitter
+                        .split(chunk)
+                        .for_each(|split| push(&mut actual, split));
+                }
+                if let Some(split) = splitter.done() {
+                    push(&mut actual, split);
+                }
+
+                assert!(actual == expected, "{:?} {:?} {}", eols, str, size);
+            }
+        }
+    }
+}
@@ -15,75 +15,34 @@ pub use line::*;
 pub use page::*;
 pub use text::Text;
 
+use std::ops::Add;
+use std::ops::AddAssign;
+
 const SPACES: &'static str = unsafe { std::str::from_utf8_unchecked(&[b' '; u8::MAX as usize]) };
+const TABS: &str = unsafe { std::str::from_utf8_unchecked(&[b'\t'; u8::MAX as usize]) };
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
 pub struct Offset {
     len:   usize,
     chars: usize,
+    utf16: usize,
     lines: usize,
 }
 
-/// Test function.
-pub fn main() {
-    let text = "Hello, world\nHow you doing?12345678901234567890\n";
-    let mut builder = text::PageBuilder::default();
-
-    let mut str = text;
+impl Add for Offset {
+    type Output = Self;
 
-    while !str.is_empty() {
-        let s = builder.push_str(str).unwrap_or("");
-
-        str = s;
-        dbg!(str);
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
     }
 }
 
-fn test1() {
-    let texts : &[&[&str]]= &[
-        &["Hello, world\nHow you doing?", "12345678901234567890\n"],
-        &[
-            "  Hello, world\n   How you doing?",
-            "  34    5678901234567890\n",
-        ],
-        &[
-            "\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n",
-        ],
-        &["11\r2\n"],
-        &["     "],
-        &["\n", "2\n"],
-    ];
-
-    for chunks in texts {
-        println!("==============================================");
-        println!("==============================================");
-        dbg!(chunks);
-        println!();
-        println!();
-
-        let mut builder = text::PageBuilder::default();
-        let mut string = String::new();
-
-        for chunk in *chunks {
-            // dbg!(chunk);
-            for page in builder.push(chunk) {
-                // dbg!(page);
-                let p = page.to_string();
-                string.push_str(&p);
-                dbg!(p);
-                // dbg!(page.chunks().collect::<Vec<_>>());
-            }
-        }
-
-        let page = builder.done();
-        let p = page.to_string();
-        string.push_str(&p);
-        dbg!(p);
-        dbg!(page.chunks().collect::<Vec<_>>());
-
-        let expected = chunks.concat();
-        println!("expected {:?}", &expected);
-        println!("string   {:?}", &string);
-        assert!(expected == string);
+impl AddAssign for Offset {
+    fn add_assign(&mut self, other: Self) {
+        self.len += other.len;
+        self.chars += other.chars;
+        self.utf16 += other.utf16;
+        self.lines += other.lines;
     }
 }
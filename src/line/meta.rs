@@ -1,18 +1,64 @@
 use crate::Eol;
+use crate::SPACES;
+use crate::TABS;
 
-// 32 bits:
-// 1  is_empty
-// 4  Option<Eol>
-// 10 len
-// 10 chars
-// 7  spaces
-
+/// The metadata of a record in a [`Page`](crate::Page): the len (in bytes)
+/// and chars of its line, its leading spaces (or tabs) and its eol.
+///
+/// A record is `ascii` when its bytes (leading spaces and eol excluded) are
+/// all printable ASCII (`' '..='~'`), hence one column each: its display width
+/// is known without decoding it. Its chars are then its len.
+///
+/// A line longer than the room left in a page spans several records, in
+/// several pages: all but the last are `continued` (and have no eol). Only the
+/// first one may have leading spaces.
+///
+/// Leading spaces are either all `' '`s or, if `tabs`, all `'\t'`s (a mixed
+/// indentation only has its first run compressed).
+///
+/// # Format
+///
+/// This encoding is part of the on-page format, at version
+/// [`FORMAT_VERSION`](crate::page::FORMAT_VERSION). Changing it requires
+/// bumping the version.
+///
+/// A record without leading spaces nor line (an eol only) takes 1 byte:
+///
+/// | bits | value                     |
+/// |------|---------------------------|
+/// | 7    | `1`                       |
+/// | 6..3 | eol (`1..=8`, see below)  |
+/// | 2..0 | `0`                       |
+///
+/// Other records take 4 bytes, a big-endian `u32`:
+///
+/// | bits   | value                   |
+/// |--------|-------------------------|
+/// | 31     | `0`                     |
+/// | 30..27 | eol (`0..=8`, or `15`)  |
+/// | 26..17 | len                     |
+/// | 16..7  | chars, or `0` if ascii  |
+/// | 6      | tabs                    |
+/// | 5..0   | spaces                  |
+///
+/// Non-empty lines have chars, so a `0` chars marks ascii records (which
+/// chars are their len), without taking a bit.
+///
+/// Eols are encoded as: `0` none, `1` LF, `2` VT, `3` FF, `4` CR, `5` NEL,
+/// `6` LS, `7` PS, `8` CRLF, while `15` marks a `continued` record (without
+/// eol). A record without eol which is not `continued` ends the text.
 #[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
 pub struct LineMeta {
-    pub len:    u16,
-    pub chars:  u16,
-    pub spaces: u8,
-    pub eol:    Option<Eol>,
+    pub len:       u16,
+    pub chars:     u16,
+    pub spaces:    u8,
+    /// Whether leading spaces are tabs.
+    pub tabs:      bool,
+    pub eol:       Option<Eol>,
+    /// Whether the line continues in the next record.
+    pub continued: bool,
+    /// Whether the bytes are all printable ASCII.
+    pub ascii:     bool,
 }
 
 macro_rules! consts {
@@ -50,14 +96,16 @@ macro_rules! consts {
 }
 
 consts!(
-    spaces(u8) : SPACES_MAX SPACES_MASK SPACES_BITS ( 7) SPACES_SHL
-    chars (u16): CHARS_MAX  CHARS_MASK  CHARS_BITS  (10) CHARS_SHL  (spaces)
+    spaces(u8) : SPACES_MAX SPACES_MASK SPACES_BITS ( 6) SPACES_SHL
+    tabs  (u8) : TABS_MAX   TABS_MASK   TABS_BITS   ( 1) TABS_SHL   (spaces)
+    chars (u16): CHARS_MAX  CHARS_MASK  CHARS_BITS  (10) CHARS_SHL  (tabs)
     len   (u16): LEN_MAX    LEN_MASK    LEN_BITS    (10) LEN_SHL    (chars)
     eol   (u8) : EOL_MAX    EOL_MASK    EOL_BITS    ( 4) EOL_SHL    (len)
 );
 
 impl LineMeta {
     pub const BYTES: usize = 4;
+    pub const CONTINUED: u8 = 15;
     pub const IS_EMPTY_MASK: u8 = 1 << (u8::BITS - 1);
 
     pub fn new(len: u16, chars: u16, spaces: u8, eol: Option<Eol>) -> Self {
@@ -76,7 +124,10 @@ impl LineMeta {
             len,
             chars,
             spaces,
+            tabs: false,
             eol,
+            continued: false,
+            ascii: len == 0,
         }
     }
 
@@ -92,6 +143,10 @@ impl LineMeta {
             } else {
                 true
             }
+            && !(self.tabs && self.spaces == 0)
+            && !(self.continued && self.eol.is_some())
+            && (self.ascii || self.len != 0)
+            && !(self.ascii && self.chars != self.len)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -102,6 +157,12 @@ impl LineMeta {
         }
     }
 
+    /// Returns the leading spaces (or tabs).
+    pub fn indent(&self) -> &'static str {
+        let indent = if self.tabs { TABS } else { SPACES };
+        &indent[..self.spaces as usize]
+    }
+
     pub fn width(&self) -> usize {
         if self.is_empty() {
             1
@@ -114,10 +175,13 @@ impl LineMeta {
         if self.is_empty() {
             [Self::IS_EMPTY_MASK | eol_to_u8(self.eol) << 3, 0, 0, 0]
         } else {
+            let chars = if self.ascii { 0 } else { self.chars };
+
             (0 | (self.spaces as u32) << Self::SPACES_SHL
-                | (self.chars as u32) << Self::CHARS_SHL
+                | (self.tabs as u32) << Self::TABS_SHL
+                | (chars as u32) << Self::CHARS_SHL
                 | (self.len as u32) << Self::LEN_SHL
-                | (eol_to_u8(self.eol) as u32) << Self::EOL_SHL)
+                | (self.eol_to_u8() as u32) << Self::EOL_SHL)
                 .to_be_bytes()
         }
     }
@@ -136,6 +200,26 @@ impl LineMeta {
         }
     }
 
+    /// Deserializes a `LineMeta` from the start of `bytes`, returning the
+    /// following bytes, or `None` if `bytes` does not start with a valid
+    /// `LineMeta`.
+    pub fn try_deserialize(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        let first = *bytes.first()?;
+
+        if first >= Self::IS_EMPTY_MASK {
+            let eol = try_u8_to_eol(first << 1 >> 4)?;
+            let unused = first & 0b111;
+
+            (eol.is_some() && unused == 0).then(|| (Self::new(0, 0, 0, eol), &bytes[1..]))
+        } else {
+            let mut u32 = [0; 4];
+            u32.copy_from_slice(bytes.get(0..4)?);
+
+            let meta = Self::from_u32(u32::from_be_bytes(u32))?;
+            (meta.is_valid() && !meta.is_empty()).then_some((meta, &bytes[4..]))
+        }
+    }
+
     pub fn deserialize_1(byte: u8) -> Self {
         debug_assert!(byte >= Self::IS_EMPTY_MASK);
 
@@ -151,10 +235,38 @@ impl LineMeta {
         let mut u32 = [0; 4];
         u32.copy_from_slice(&bytes[0..4]);
 
-        let (spaces, chars, len, eol) = Self::deser_u32(u32::from_be_bytes(u32));
-        debug_assert!(spaces as u16 + chars + len > 0);
+        let meta = Self::from_u32(u32::from_be_bytes(u32));
+        debug_assert!(meta.is_some_and(|meta| meta.is_valid() && !meta.is_empty()));
+
+        match meta {
+            Some(meta) => meta,
+            None => unreachable!(),
+        }
+    }
+
+    /// Decodes a 4 bytes `LineMeta`, or `None` if its eol is invalid.
+    fn from_u32(u32: u32) -> Option<Self> {
+        let (spaces, tabs, chars, len, eol) = Self::deser_u32(u32);
+        let continued = eol == Self::CONTINUED;
+        let ascii = chars == 0;
+
+        Some(Self {
+            len,
+            chars: if ascii { len } else { chars },
+            spaces,
+            tabs: tabs != 0,
+            eol: if continued { None } else { try_u8_to_eol(eol)? },
+            continued,
+            ascii,
+        })
+    }
 
-        Self::new(len, chars, spaces, u8_to_eol(eol))
+    fn eol_to_u8(&self) -> u8 {
+        if self.continued {
+            Self::CONTINUED
+        } else {
+            eol_to_u8(self.eol)
+        }
     }
 }
 
@@ -168,10 +280,17 @@ macro_rules! eol_from_to_u8 {
         }
 
         fn u8_to_eol(u8: u8) -> Option<Eol> {
+            match try_u8_to_eol(u8) {
+                Some(eol) => eol,
+                None => unreachable!(),
+            }
+        }
+
+        fn try_u8_to_eol(u8: u8) -> Option<Option<Eol>> {
             match u8 {
-                $none => None,
-                $($u8 => Some(Eol::$Eol),)*
-                _ => unreachable!(),
+                $none => Some(None),
+                $($u8 => Some(Some(Eol::$Eol)),)*
+                _ => None,
             }
         }
     };
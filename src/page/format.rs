@@ -0,0 +1,169 @@
+use super::Page;
+use super::PageRef;
+use super::Record;
+use super::BYTES;
+use crate::utils;
+use crate::LineMeta;
+use crate::Offset;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::ops::Index;
+use std::ops::RangeFull;
+use std::str::from_utf8;
+
+/// Version of the on-page format: the page header and the [`LineMeta`]
+/// encoding.
+///
+/// - `1`: initial format
+/// - `2`: tabs flag in [`LineMeta`] (at most 63 spaces)
+/// - `3`: `0` chars marking ascii records in [`LineMeta`]
+/// - `4`: `15` eol marking continued records in [`LineMeta`]
+pub const FORMAT_VERSION: u16 = 4;
+
+/// Len of a serialized page's header.
+pub const HEADER: usize = 16;
+
+/// Len of a serialized page: [`HEADER`] then [`BYTES`].
+pub const SERIALIZED: usize = HEADER + BYTES;
+
+/// An error raised when deserializing a [`Page`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FormatError {
+    /// Not [`SERIALIZED`] bytes long.
+    Len { len: usize },
+    /// Unsupported format version.
+    Version { version: u16 },
+    /// Page bytes of a different len than [`BYTES`].
+    Bytes { bytes: u16 },
+    /// Invalid records, or records not matching the header.
+    Records,
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Len { len } => write!(f, "invalid page len {} (expected {})", len, SERIALIZED),
+            Self::Version { version } => write!(f, "unsupported page format version {}", version),
+            Self::Bytes { bytes } => write!(f, "invalid page size {} (expected {})", bytes, BYTES),
+            Self::Records => write!(f, "invalid page records"),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl<T> Page<T>
+where
+    Self: Index<RangeFull, Output = [u8]>,
+{
+    /// Serializes this page, in the format at [`FORMAT_VERSION`].
+    ///
+    /// The header is made of little-endian `u16`s: format version, len of
+    /// the page bytes ([`BYTES`]), `end`, `len`, `chars`, `utf16`, `lines`
+    /// and `first`. Page bytes follow, as a sequence of records (a
+    /// [`LineMeta`] and the line) up to `end`, then zeros.
+    pub fn to_bytes(&self) -> [u8; SERIALIZED] {
+        let mut bytes = [0; SERIALIZED];
+        let header = [
+            FORMAT_VERSION,
+            BYTES as u16,
+            self.end,
+            self.len,
+            self.chars,
+            self.utf16,
+            self.lines,
+            self.first,
+        ];
+
+        for (bytes, u16) in bytes.chunks_exact_mut(2).zip(header) {
+            bytes.copy_from_slice(&u16.to_le_bytes());
+        }
+
+        let end = self.end as usize;
+        bytes[HEADER..][..end].copy_from_slice(&self[..][..end]);
+        bytes
+    }
+}
+
+impl Page {
+    /// Deserializes a page from `bytes` (see [`Page::to_bytes`]).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        let page = PageRef::view(bytes)?;
+        let mut bytes = [0; BYTES];
+        bytes.copy_from_slice(page.bytes);
+
+        Ok(page.with_bytes(bytes))
+    }
+}
+
+impl<'a> PageRef<'a> {
+    /// Deserializes a page from `bytes` (see [`Page::to_bytes`]), without
+    /// copying, e.g. from a memory-mapped file.
+    ///
+    /// Records are validated, so that the page can be read safely.
+    pub fn view(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        if bytes.len() != SERIALIZED {
+            return Err(FormatError::Len { len: bytes.len() });
+        }
+
+        let mut header = [0; HEADER / 2];
+        for (u16, bytes) in header.iter_mut().zip(bytes.chunks_exact(2)) {
+            *u16 = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+
+        let [version, size, end, len, chars, utf16, lines, first] = header;
+        if version != FORMAT_VERSION {
+            return Err(FormatError::Version { version });
+        }
+        if size as usize != BYTES {
+            return Err(FormatError::Bytes { bytes: size });
+        }
+        if end as usize > BYTES {
+            return Err(FormatError::Records);
+        }
+
+        let page = Self {
+            first,
+            end,
+            len,
+            chars,
+            utf16,
+            lines,
+            bytes: &bytes[HEADER..],
+        };
+
+        match validate(&page.bytes[..end as usize]) {
+            Some(size) if size == page.size() => Ok(page),
+            _ => Err(FormatError::Records),
+        }
+    }
+}
+
+/// Returns the size of `records`, or `None` if invalid.
+fn validate(mut records: &[u8]) -> Option<Offset> {
+    let mut size = Offset::default();
+
+    while !records.is_empty() {
+        let (meta, after) = LineMeta::try_deserialize(records)?;
+        let line = from_utf8(after.get(..meta.len as usize)?).ok()?;
+
+        if line.chars().count() != meta.chars as usize
+            || line.bytes().all(|byte| utils::is_ascii1(&byte)) != meta.ascii
+        {
+            return None;
+        }
+
+        size += Record {
+            index: 0,
+            meta,
+            str: line,
+        }
+        .offset();
+
+        records = &after[line.len()..];
+    }
+
+    Some(size)
+}
@@ -1,70 +1,227 @@
 // mod builder;
+mod format;
+mod pointer;
 
 // pub use builder::*;
+pub use format::*;
+pub use pointer::*;
+
+#[cfg(test)]
+mod tests;
 
 use super::LineMeta;
 use crate::Offset;
-use crate::SPACES;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::ops::Index;
 use std::ops::IndexMut;
-use std::rc::Rc;
+use std::ops::RangeFull;
 use std::slice::SliceIndex;
 
-// pub(super) const BYTES: usize = 1024; // TODO - 2*size<usize>
-pub(super) const BYTES: usize = 10;
+/// Len of the bytes of a [`Page`], so that an [`RcPage`]'s allocation (bytes
+/// and two counters) is 1 KiB.
+///
+/// This is part of the on-page format (see [`FORMAT_VERSION`]): it is written
+/// in serialized pages, and checked when reading them.
+///
+/// The size is deliberately not configurable: a cargo feature would not be
+/// additive (enabled by any crate of the dependency graph, it would change
+/// pages and their format for all), and a `Page<const N>` would spread to
+/// every type holding pages, for no measured gain.
+#[cfg(not(test))]
+pub const BYTES: usize = 1024 - 2 * std::mem::size_of::<usize>();
+/// Pages are only 10 bytes long in unit tests, to exercise page boundaries
+/// (the format is also tested at the real size in `tests/format.rs`).
+#[cfg(test)]
+pub const BYTES: usize = 10;
+
+// A page's len must fit in a `u16`, even with 1 byte records of 3 bytes eols
+// (e.g. `'\u{2028}'`), or 4 bytes records of spaces and eol
+const _: () = assert!(BYTES * 3 <= u16::MAX as usize);
+const _: () =
+    assert!(BYTES / LineMeta::BYTES * (LineMeta::SPACES_MAX as usize + 3) <= u16::MAX as usize);
+// A record's len is bounded by the page, never by its `LineMeta`
+const _: () = assert!(BYTES - LineMeta::BYTES <= LineMeta::LEN_MAX as usize);
 
 pub type PageRef<'a> = Page<&'a [u8]>;
 pub type PageMut<'a> = Page<&'a mut [u8]>;
-pub type RcPage = Page<Rc<[u8; BYTES]>>;
+pub type SharedPage<P> = Page<Bytes<P>>;
+pub type RcPage = SharedPage<RcKind>;
+pub type ArcPage = SharedPage<ArcKind>;
 
+/// A page of lines.
+///
+/// `bytes` is a sequence of records (up to `end`), each a serialized
+/// [`LineMeta`] followed by the line's bytes (without leading spaces/tabs nor
+/// eol).
+/// A `continued` record is followed by the rest of its line, in the next
+/// record (possibly in the next page).
+///
+/// `len`, `chars`, `utf16` (UTF-16 code units) and `lines` (the number of eols)
+/// sum up the records.
 #[derive(Copy, Clone, Debug)]
 pub struct Page<T = [u8; BYTES]> {
-    pub(super) offset: Offset,
-    pub(super) first:  u16,
-    pub(super) end:    u16,
-    pub(super) len:    u16,
-    pub(super) chars:  u16,
-    pub(super) lines:  u16,
-    pub(super) bytes:  T, // [u8; 1008]
+    pub(super) first: u16,
+    pub(super) end:   u16,
+    pub(super) len:   u16,
+    pub(super) chars: u16,
+    pub(super) utf16: u16,
+    pub(super) lines: u16,
+    pub(super) bytes: T,
 }
 
 impl Default for Page {
     fn default() -> Self {
         Self {
-            offset: Default::default(),
-            first:  0,
-            end:    0,
-            len:    0,
-            chars:  0,
-            lines:  0,
-            bytes:  [0; BYTES],
+            first: 0,
+            end:   0,
+            len:   0,
+            chars: 0,
+            utf16: 0,
+            lines: 0,
+            bytes: [0; BYTES],
         }
     }
 }
 
-impl Page {
+impl<T> Page<T> {
+    /// Returns a page with the same records, in `bytes`.
+    fn with_bytes<U>(&self, bytes: U) -> Page<U> {
+        Page {
+            first: self.first,
+            end: self.end,
+            len: self.len,
+            chars: self.chars,
+            utf16: self.utf16,
+            lines: self.lines,
+            bytes,
+        }
+    }
+}
+
+impl<T> Page<T>
+where
+    Self: Index<RangeFull, Output = [u8]>,
+{
+    /// Returns the size of this page, as an [`Offset`].
+    pub fn size(&self) -> Offset {
+        Offset {
+            len:   self.len as usize,
+            chars: self.chars as usize,
+            utf16: self.utf16 as usize,
+            lines: self.lines as usize,
+        }
+    }
+
+    /// Returns `true` if the last record is continued in the next page.
+    pub fn is_continued(&self) -> bool {
+        self.records(0)
+            .last()
+            .is_some_and(|record| record.meta.continued)
+    }
+
+    /// Returns an iterator over the `&str` chunks of this page.
     pub fn chunks(&self) -> impl Iterator<Item = &str> {
-        let mut bytes = &self[..];
+        self.records(0).flat_map(|record| record.chunks())
+    }
 
-        std::iter::from_fn(move || {
-            if bytes.len() > BYTES - self.end as usize {
-                let (meta, after) = LineMeta::deserialize(bytes);
-                let (line, after) = after.split_at(meta.len as usize);
-                bytes = after;
+    /// Returns an iterator over the [`Record`]s of this page,
+    /// starting at byte `index`.
+    pub fn records(&self, mut index: usize) -> impl Iterator<Item = Record<'_>> {
+        debug_assert!(index <= self.end as usize);
 
-                let spaces = (meta.spaces != 0).then(|| &SPACES[0..meta.spaces as usize]);
-                let line = (line.len() > 0).then(|| unsafe { utf8!(line) });
-                let eol = meta.eol.map(|eol| eol.as_str());
+        std::iter::from_fn(move || {
+            if index < self.end as usize {
+                let (meta, after) = LineMeta::deserialize(&self[..][index..]);
+                let line = unsafe { utf8!(&after[..meta.len as usize]) };
+                let record = Record {
+                    index,
+                    meta,
+                    str: line,
+                };
 
-                Some(spaces.into_iter().chain(line).chain(eol))
+                index = record.end();
+                Some(record)
             } else {
                 None
             }
         })
-        .flatten()
     }
 }
 
+/// A record of a [`Page`]: a [`LineMeta`] and its line.
+#[derive(Copy, Clone, Debug)]
+pub struct Record<'a> {
+    /// The byte index of the record in the page.
+    pub index: usize,
+    /// The [`LineMeta`].
+    pub meta:  LineMeta,
+    /// The line, without leading spaces nor eol.
+    pub str:   &'a str,
+}
+
+impl<'a> Record<'a> {
+    /// Returns the byte index following this record in the page.
+    pub fn end(&self) -> usize {
+        self.index + self.meta.width() + self.meta.len as usize
+    }
+
+    /// Returns the len (in bytes) of the record,
+    /// including leading spaces and eol.
+    pub fn len(&self) -> usize {
+        self.meta.spaces as usize
+            + self.str.len()
+            + self.meta.eol.map_or(0, |eol| eol.as_str().len())
+    }
+
+    /// Returns the len (in UTF-16 code units) of the record,
+    /// including leading spaces and eol.
+    pub fn utf16(&self) -> usize {
+        self.chars() + surrogates(self.str.as_bytes())
+    }
+
+    /// Returns the size of the record, as an [`Offset`] (`lines` being the
+    /// number of eols).
+    pub fn offset(&self) -> Offset {
+        Offset {
+            len:   self.len(),
+            chars: self.chars(),
+            utf16: self.utf16(),
+            lines: self.meta.eol.is_some() as usize,
+        }
+    }
+
+    /// Returns `true` if the record has no leading spaces, line nor eol.
+    pub fn is_empty(&self) -> bool {
+        self.meta.is_empty() && self.meta.eol.is_none()
+    }
+
+    /// Returns the len (in chars) of the record,
+    /// including leading spaces and eol.
+    pub fn chars(&self) -> usize {
+        self.meta.spaces as usize
+            + self.meta.chars as usize
+            + self.meta.eol.map_or(0, |eol| eol.as_chars().len())
+    }
+
+    /// Returns an iterator over the `&str` chunks of this record:
+    /// leading spaces, line and eol.
+    pub fn chunks(&self) -> impl Iterator<Item = &'a str> {
+        let spaces = (self.meta.spaces != 0).then(|| self.meta.indent());
+        let line = (!self.str.is_empty()).then_some(self.str);
+        let eol = self.meta.eol.map(|eol| eol.as_str());
+
+        spaces.into_iter().chain(line).chain(eol)
+    }
+}
+
+/// Returns the number of chars encoded with surrogate pairs in UTF-16
+/// (i.e. 4 bytes long in UTF-8) in (valid UTF-8) `bytes`.
+pub(crate) fn surrogates(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&byte| byte >= 0xF0).count()
+}
+
 macro_rules! index {
     (mut $Type:ident$(<$lifetime:lifetime>)?) => {
         impl<$($lifetime,)? I> IndexMut<I> for $Type$(<$lifetime>)?
@@ -93,45 +250,54 @@ macro_rules! index {
 index!(Page);
 index!(PageRef<'a>);
 index!(PageMut<'a>);
-index!(RcPage);
 index!(mut Page);
 index!(mut PageMut<'a>);
 
-impl From<Page> for RcPage {
-    fn from(page: Page) -> Self {
-        Self {
-            offset: page.offset,
-            first:  page.first,
-            end:    page.end,
-            len:    page.len,
-            chars:  page.chars,
-            lines:  page.lines,
-            bytes:  Rc::new(page.bytes),
-        }
+impl<P: PointerKind, I> Index<I> for SharedPage<P>
+where
+    I: SliceIndex<[u8]>,
+{
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &I::Output {
+        &self.bytes[index]
     }
 }
 
-impl ToString for Page {
-    fn to_string(&self) -> String {
-        use super::LineMeta;
-
-        let mut string = String::with_capacity(self.len as usize);
-        let mut bytes = &self.bytes[..];
+impl<P: PointerKind> SharedPage<P> {
+    /// Returns this page with its bytes behind a `Q` pointer, moving them if
+    /// not shared (see [`Bytes::into_kind`]).
+    pub fn into_kind<Q: PointerKind>(self) -> SharedPage<Q> {
+        let page = self.with_bytes(());
+        page.with_bytes(self.bytes.into_kind())
+    }
+}
 
-        loop {
-            if bytes.len() <= BYTES - self.end as usize {
-                break;
-            }
+impl<P: PointerKind> From<Page> for SharedPage<P> {
+    fn from(page: Page) -> Self {
+        page.with_bytes(Bytes::new(page.bytes))
+    }
+}
 
-            let (meta, after) = LineMeta::deserialize(bytes);
-            let (line, after) = after.split_at(meta.len as usize);
-            bytes = after;
+/// Copies the bytes of `page`.
+impl From<&ArcPage> for RcPage {
+    fn from(page: &ArcPage) -> Self {
+        page.with_bytes(Bytes::new(*page.bytes))
+    }
+}
 
-            (0..meta.spaces).for_each(|_| string.push(' '));
-            string.push_str(unsafe { utf8!(line) });
-            meta.eol.map(|eol| string.push_str(eol.as_str()));
-        }
+/// Copies the bytes of `page`.
+impl From<&RcPage> for ArcPage {
+    fn from(page: &RcPage) -> Self {
+        page.with_bytes(Bytes::new(*page.bytes))
+    }
+}
 
-        string
+impl<T> Display for Page<T>
+where
+    Self: Index<RangeFull, Output = [u8]>,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
     }
 }
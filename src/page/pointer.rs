@@ -0,0 +1,131 @@
+use super::BYTES;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A kind of shared pointer, [`Rc`] ([`RcKind`]) or [`Arc`] ([`ArcKind`]),
+/// holding the bytes of [`SharedPage`](super::SharedPage)s and the nodes of
+/// page trees.
+pub trait PointerKind: Copy + Default + Debug + 'static {
+    /// A shared pointer to a `T`.
+    type Pointer<T>: Clone + Deref<Target = T>;
+
+    /// Returns a pointer to `value`.
+    fn new<T>(value: T) -> Self::Pointer<T>;
+
+    /// Returns a mutable reference to the value of `this`, cloning it first if
+    /// it is shared (see [`Rc::make_mut`]).
+    fn make_mut<T: Clone>(this: &mut Self::Pointer<T>) -> &mut T;
+
+    /// Returns the value of `this` if it is not shared, or `this` otherwise.
+    fn try_unwrap<T>(this: Self::Pointer<T>) -> Result<T, Self::Pointer<T>>;
+
+    /// Returns `true` if `this` and `other` point to the same value.
+    fn ptr_eq<T>(this: &Self::Pointer<T>, other: &Self::Pointer<T>) -> bool;
+}
+
+/// [`Rc`] pointers, for texts used on a single thread.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct RcKind;
+
+/// [`Arc`] pointers, for texts sent and shared across threads.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct ArcKind;
+
+impl PointerKind for RcKind {
+    type Pointer<T> = Rc<T>;
+
+    fn new<T>(value: T) -> Rc<T> {
+        Rc::new(value)
+    }
+
+    fn make_mut<T: Clone>(this: &mut Rc<T>) -> &mut T {
+        Rc::make_mut(this)
+    }
+
+    fn try_unwrap<T>(this: Rc<T>) -> Result<T, Rc<T>> {
+        Rc::try_unwrap(this)
+    }
+
+    fn ptr_eq<T>(this: &Rc<T>, other: &Rc<T>) -> bool {
+        Rc::ptr_eq(this, other)
+    }
+}
+
+impl PointerKind for ArcKind {
+    type Pointer<T> = Arc<T>;
+
+    fn new<T>(value: T) -> Arc<T> {
+        Arc::new(value)
+    }
+
+    fn make_mut<T: Clone>(this: &mut Arc<T>) -> &mut T {
+        Arc::make_mut(this)
+    }
+
+    fn try_unwrap<T>(this: Arc<T>) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(this)
+    }
+
+    fn ptr_eq<T>(this: &Arc<T>, other: &Arc<T>) -> bool {
+        Arc::ptr_eq(this, other)
+    }
+}
+
+/// The bytes of a [`SharedPage`](super::SharedPage), shared behind a `P`
+/// pointer.
+pub struct Bytes<P: PointerKind>(P::Pointer<[u8; BYTES]>);
+
+impl<P: PointerKind> Bytes<P> {
+    /// Returns `bytes`, behind a new pointer.
+    pub fn new(bytes: [u8; BYTES]) -> Self {
+        Self(P::new(bytes))
+    }
+
+    /// Returns the bytes mutably, copying them first if they are shared.
+    pub fn make_mut(&mut self) -> &mut [u8; BYTES] {
+        P::make_mut(&mut self.0)
+    }
+
+    /// Returns the bytes behind a `Q` pointer, moving them if not shared, or
+    /// copying them otherwise.
+    pub fn into_kind<Q: PointerKind>(self) -> Bytes<Q> {
+        match P::try_unwrap(self.0) {
+            Ok(bytes) => Bytes::new(bytes),
+            Err(bytes) => Bytes::new(*bytes),
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are the same shared bytes.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        P::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns a pointer to the bytes, which identifies them while shared.
+    pub fn as_ptr(&self) -> *const [u8; BYTES] {
+        &*self.0
+    }
+}
+
+impl<P: PointerKind> Deref for Bytes<P> {
+    type Target = [u8; BYTES];
+
+    fn deref(&self) -> &[u8; BYTES] {
+        &self.0
+    }
+}
+
+impl<P: PointerKind> Clone for Bytes<P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<P: PointerKind> Debug for Bytes<P> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
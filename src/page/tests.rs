@@ -0,0 +1,117 @@
+use super::*;
+use crate::Text;
+
+test_files!("../../../../");
+
+#[test]
+fn format() {
+    for str in FILES {
+        for (_, page) in &Text::from(*str).pages {
+            let bytes = page.to_bytes();
+            let copy = Page::from_bytes(&bytes).unwrap();
+            let borrowed = PageRef::view(&bytes).unwrap();
+
+            assert!(copy.size() == page.size());
+            assert!(borrowed.size() == page.size());
+            assert!(copy.to_bytes() == bytes);
+            assert!(borrowed.chunks().eq(page.chunks()));
+
+            let len = SERIALIZED - 1;
+            assert!(PageRef::view(&bytes[..len]).err() == Some(FormatError::Len { len }));
+
+            let mut corrupted = bytes;
+            corrupted[0] = 0;
+            assert!(PageRef::view(&corrupted).err() == Some(FormatError::Version { version: 0 }));
+
+            let mut corrupted = bytes;
+            corrupted[2] += 1;
+            assert!(matches!(
+                PageRef::view(&corrupted),
+                Err(FormatError::Bytes { .. })
+            ));
+
+            let mut corrupted = bytes;
+            corrupted[6] += 1;
+            assert!(PageRef::view(&corrupted).err() == Some(FormatError::Records));
+
+            // An `ascii` flag not matching the content
+            if let Some(record) = page.records(0).find(|record| record.meta.len != 0) {
+                let meta = LineMeta {
+                    ascii: !record.meta.ascii,
+                    ..record.meta
+                };
+                let mut corrupted = bytes;
+                corrupted[HEADER + record.index..][..LineMeta::BYTES]
+                    .copy_from_slice(&meta.serialize());
+                assert!(PageRef::view(&corrupted).err() == Some(FormatError::Records));
+            }
+
+            let mut corrupted = bytes;
+            corrupted[HEADER] = 0xFF;
+            assert!(PageRef::view(&corrupted).err() == Some(FormatError::Records));
+        }
+    }
+}
+
+#[test]
+fn golden() {
+    // 64 spaces (63 compressed) then tabs
+    assert_golden(&format!("{}a\n\t\tb\n", " ".repeat(64)), &[
+        [
+            4, 0, 10, 0, 10, 0, 68, 0, 68, 0, 68, 0, 1, 0, 0, 0, // Header
+            8, 4, 0, 63, 32, 97, // LF, len 2, 63 spaces, " a"
+            120, 0, 0, 66, // Continued, len 0, 2 tabs
+        ],
+        [
+            4, 0, 10, 0, 5, 0, 2, 0, 2, 0, 2, 0, 1, 0, 0, 0, // Header
+            8, 2, 0, 0, 98, // LF, len 1, "b"
+            0, 0, 0, 0, 0,
+        ],
+    ]);
+
+    // Ascii records have `0` chars, others their chars
+    assert_golden("  é\n\n", &[[
+        4, 0, 10, 0, 8, 0, 6, 0, 5, 0, 5, 0, 2, 0, 0, 0, // Header
+        8, 6, 1, 1, 32, 195, 169, // LF, len 3, 2 chars, 1 space, " é"
+        136, // LF
+        0, 0,
+    ]]);
+    assert_golden("ab\r\n", &[[
+        4, 0, 10, 0, 6, 0, 4, 0, 4, 0, 4, 0, 1, 0, 0, 0, // Header
+        64, 4, 0, 0, 97, 98, // CRLF, len 2, ascii, "ab"
+        0, 0, 0, 0,
+    ]]);
+
+    // A line continued in the next page
+    assert_golden("abcdefghij\n", &[
+        [
+            4, 0, 10, 0, 10, 0, 6, 0, 6, 0, 6, 0, 0, 0, 0, 0, // Header
+            120, 12, 0, 0, 97, 98, 99, 100, 101, 102, // Continued, len 6, "abcdef"
+        ],
+        [
+            4, 0, 10, 0, 8, 0, 5, 0, 5, 0, 5, 0, 1, 0, 0, 0, // Header
+            8, 8, 0, 0, 103, 104, 105, 106, // LF, len 4, "ghij"
+            0, 0,
+        ],
+    ]);
+}
+
+/// Asserts that the pages of `str` serialize to `expected`.
+fn assert_golden(str: &str, expected: &[[u8; SERIALIZED]]) {
+    let text = Text::from(str);
+
+    assert!(text.pages.len() == expected.len());
+    for ((_, page), expected) in text.pages.iter().zip(expected) {
+        assert!(page.to_bytes() == *expected, "{:?}", page.to_bytes());
+        assert!(Page::from_bytes(expected)
+            .unwrap()
+            .chunks()
+            .eq(page.chunks()));
+
+        // Previous versions are not read as this one
+        let version = FORMAT_VERSION - 1;
+        let mut previous = *expected;
+        previous[..2].copy_from_slice(&version.to_le_bytes());
+        assert!(PageRef::view(&previous).err() == Some(FormatError::Version { version }));
+    }
+}
@@ -1,194 +1,263 @@
 use super::page;
 use super::Eol;
+use super::EolSet;
 use super::Page;
 use super::Text;
+use super::TextIn;
 use crate::line::LineMeta;
-use crate::Offset;
-
+use crate::page::ArcKind;
+use crate::page::PointerKind;
+use crate::page::RcKind;
+use crate::utils;
+
+/// Builds a [`TextIn`] from a stream of `&str` chunks: see [`TextBuilder`]
+/// and [`SyncTextBuilder`].
+///
+/// Chunks can be split anywhere (on `char` boundaries), even in the middle of
+/// a `"\r\n"`.
+///
+/// # Usage
+///
+/// ```
+/// # use grappe::text::TextBuilder;
+/// let mut builder = TextBuilder::default();
+///
+/// builder.push("Hello,\r");
+/// builder.push("\n  world!");
+///
+/// let text = builder.done();
+/// assert!(text.to_string() == "Hello,\r\n  world!");
+/// assert!(text.lines == 1);
+/// ```
 #[derive(Default, Debug)]
-pub struct TextBuilder {
-    text: Text,
-    page: Page,
+pub struct TextBuilderIn<P: PointerKind = RcKind> {
+    text: TextIn<P>,
+    page: PageBuilder,
 }
 
-impl TextBuilder {
-    pub fn push(&mut self, str: &str) {}
+/// Builds a [`Text`].
+pub type TextBuilder = TextBuilderIn<RcKind>;
+
+/// Builds a [`SyncText`](super::SyncText).
+pub type SyncTextBuilder = TextBuilderIn<ArcKind>;
+
+impl<P: PointerKind> TextBuilderIn<P> {
+    /// Creates a new builder, recognizing only `eols` (see [`EolSet`]).
+    pub fn with_eols(eols: EolSet) -> Self {
+        Self {
+            text: TextIn {
+                eols,
+                ..Default::default()
+            },
+            page: PageBuilder::with_eols(eols),
+        }
+    }
+
+    /// Feeds a chunk.
+    pub fn push(&mut self, str: &str) {
+        for page in self.page.push(str) {
+            self.text.push(page.into());
+        }
+    }
+
+    /// Returns the built [`TextIn`].
+    ///
+    /// Call after feeding all chunks.
+    pub fn done(mut self) -> TextIn<P> {
+        let page = self.page.done();
+
+        if page.len != 0 {
+            self.text.push(page.into());
+        }
 
-    pub fn done(mut self) -> Text {
         self.text
     }
 }
 
 // =================
 
+/// Builds [`Page`]s from a stream of `&str` chunks.
+///
+/// Lines which do not fit in the current page are split in several records:
+/// all but the last have no [`Eol`] (see [`Page`]).
 #[derive(Default, Debug)]
 pub struct PageBuilder {
-    page:  Page,
-    index: usize,
-    meta:  LineMeta,
+    page:      Page,
+    index:     usize,
+    meta:      LineMeta,
+    continued: bool,
+    /// A space (or tab) to write before the next char.
+    space:     Option<char>,
+    eols:      EolSet,
 }
 
 impl PageBuilder {
-    pub fn push_str<'a>(&mut self, mut str: &'a str) -> Option<&'a str> {
-        if let Some(eol) = self.meta.eol {
-            if eol == Eol::CR && str.starts_with("\n") {
+    /// Creates a new `PageBuilder`, recognizing only `eols` (see [`EolSet`]).
+    pub fn with_eols(eols: EolSet) -> Self {
+        Self::new(eols, false)
+    }
+
+    /// Creates a new `PageBuilder`, recognizing only `eols`, whose first page
+    /// starts in the middle of a line if `continued`.
+    pub(crate) fn new(eols: EolSet, continued: bool) -> Self {
+        Self {
+            continued,
+            eols,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of bytes used in the current page.
+    pub(crate) fn used(&self) -> usize {
+        self.index
+    }
+
+    /// Feeds a chunk, returning an iterator over the filled [`Page`]s.
+    ///
+    /// The returned iterator must be exhausted
+    /// before calling this function again.
+    pub fn push<'a>(&'a mut self, mut str: &'a str) -> impl 'a + Iterator<Item = Page> {
+        std::iter::from_fn(move || loop {
+            if str.is_empty() {
+                return None;
+            }
+
+            let (page, rest) = self.step(str);
+            str = rest;
+
+            if page.is_some() {
+                return page;
+            }
+        })
+    }
+
+    /// Returns the last [`Page`].
+    ///
+    /// Call after feeding all chunks.
+    pub fn done(mut self) -> Page {
+        if self.meta.eol.is_some() || !self.meta.is_empty() {
+            self.advance();
+        }
+
+        self.page
+    }
+
+    /// Pushes as much of `str` as possible in the current record,
+    /// returning the page if it got filled, and the rest of `str`.
+    fn step<'a>(&mut self, mut str: &'a str) -> (Option<Page>, &'a str) {
+        // Pending `'\r'`, waiting for a potential `'\n'`
+        if self.meta.eol == Some(Eol::CR) {
+            if let Some(tail) = str.strip_prefix('\n') {
                 self.meta.eol = Some(Eol::CRLF);
-                str = &str[1..];
+                str = tail;
             }
 
             self.advance();
-            return Some(str);
-        } else if self.meta.spaces == 0 && self.meta.len == 0 {
-            return Some(str);
-        } else {
-            if self.meta.len == 0 {
-                str = self.push_spaces(str);
+            return (None, str);
+        }
+
+        // Content `'\r'` ending the previous chunk, then `'\n'` (only when
+        // `"\r\n"` is recognized, but not `'\r'`)
+        if str.starts_with('\n') && self.ends_with_cr() {
+            self.meta.len -= 1;
+            self.meta.chars -= 1;
+            self.meta.eol = Some(Eol::CRLF);
+            self.advance();
+            return (None, &str[1..]);
+        }
+
+        // Leading spaces (or tabs)
+        if self.is_line_start() && str.starts_with([' ', '\t']) {
+            if self.meta.is_empty() && !self.fits(LineMeta::BYTES) {
+                return (Some(self.flush()), str);
             }
 
-            let end = self.index + LineMeta::BYTES + self.meta.len as usize;
-            str = self.push_line(str, page::BYTES - end);
+            str = self.push_spaces(str);
 
             if str.is_empty() {
-                return None;
-            } else {
-                Some(str)
+                return (None, str);
             }
         }
-    }
 
-    pub fn push<'a>(&'a mut self, mut str: &'a str) -> impl 'a + Iterator<Item = Page> {
-        dbg!(str);
+        let end = self.index + LineMeta::BYTES + self.meta.len as usize;
+        let max = page::BYTES.saturating_sub(end);
+
+        // Only look for an eol in the room left in the page
+        let (line, eol) = self.eols.split_within(str, max);
+
+        if !line.is_empty() {
+            // Keep the last space with the following non-ASCII char
+            // (e.g. combining marks), to not split clusters
+            if self.is_line_start() && self.meta.spaces != 0 && !line.as_bytes()[0].is_ascii() {
+                self.space = Some(if self.meta.tabs { '\t' } else { ' ' });
+                self.meta.spaces -= 1;
+                self.meta.tabs &= self.meta.spaces != 0;
+            }
 
-        let mut first = None;
+            if max == 0 {
+                return (Some(self.flush()), str);
+            }
 
-        if let Some(eol) = self.meta.eol {
-            dbg!(eol);
-            if eol == Eol::CR && str.starts_with("\n") {
-                self.meta.eol = Some(Eol::CRLF);
-                str = &str[1..];
+            if let Some(space) = self.space.take() {
+                self.write(space.encode_utf8(&mut [0; 1]));
+                return (None, str);
             }
-        } else {
-            // TODO if at start of line only
-            println!("no eol");
-            if let (Some(eol), tail) = Eol::starts_with(str) {
-                if self.index < page::BYTES {
-                    self.push_eol(Some(eol));
-                    str = tail;
-                } else {
-                    first = Some(self.flush());
-                }
-            } else {
-                let end = self.index + LineMeta::BYTES + self.meta.len as usize;
-                if end < page::BYTES {
-                    println!("la fisdep");
-
-                    if self.meta.len == 0 {
-                        str = self.push_spaces(str);
-                    }
-                    str = self.push_line(str, page::BYTES - end);
-                    // self.advance();
-                } else {
-                    println!("batar {} {:#?}", self.index, self.meta);
-                    first = Some(self.flush());
-                }
-            };
-        }
 
-        dbg!(str);
-        dbg!(first.is_some());
-
-        first.into_iter().chain(std::iter::from_fn(move || {
-            loop {
-                println!("LOOP {:?} {:#?}", str, self.meta);
-                if str.is_empty() {
-                    return None;
-                }
-
-                // if self.index != 0 {
-                // self.advance();
-                // }
-
-                str = if let (Some(eol), str) = Eol::starts_with(str) {
-                    if self.index < page::BYTES {
-                        // self.meta.eol = Some(eol);
-                        self.push_eol(Some(eol));
-                        str
-                    } else {
-                        println!("flush 1");
-                        return Some(self.flush());
-                    }
-                } else {
-                    println!("ici");
-                    // str = self.push_spaces(str);
-
-                    debug_assert!(self.meta.len == 0);
-                    let end = self.index + LineMeta::BYTES;
-                    if end < page::BYTES {
-                        str = self.push_spaces(str);
-
-                        self.push_line(str, page::BYTES - end)
-                    } else {
-                        println!("flush 4");
-                        return Some(self.flush());
-                    }
-                };
-                // self.advance();
+            let (left, _) = split_at(line, max);
+            if left.is_empty() {
+                return (Some(self.flush()), str);
             }
 
-            None
-        }))
-    }
+            self.write(left);
+            return (None, &str[left.len()..]);
+        }
 
-    pub fn done(mut self) -> Page {
-        // if self.meta.is_valid() {
-        self.advance();
-        // }
-        self.page
-    }
+        let (eol, tail) = eol.expect("non empty str");
 
-    fn push_spaces<'a>(&mut self, str: &'a str) -> &'a str {
-        debug_assert!(self.meta.len == 0);
-        debug_assert!(self.meta.eol.is_none());
+        if self.meta.is_empty() && !self.fits(1) {
+            return (Some(self.flush()), str);
+        }
 
-        let max = LineMeta::SPACES_MAX - self.meta.spaces;
-        let (spaces, str) = split_spaces(str, max as usize);
+        self.meta.eol = Some(eol);
+        if eol != Eol::CR || !tail.is_empty() || !self.eols.contains(Eol::CRLF) {
+            self.advance();
+        }
 
-        self.meta.spaces += spaces.len() as u8;
-        str
+        (None, tail)
     }
 
-    fn push_line<'a>(&mut self, str: &'a str, at: usize) -> &'a str {
-        let (left, right) = split_at(str, at);
-        let (line, eol) = Eol::split(left);
-
-        self.write(line);
+    fn push_spaces<'a>(&mut self, str: &'a str) -> &'a str {
+        debug_assert!(self.meta.len == 0);
+        debug_assert!(self.meta.eol.is_none());
 
-        let (eol, str) = if let Some((eol, _)) = eol {
-            (Some(eol), &str[line.len() + eol.as_bytes().len()..])
-        } else if let (Some(eol), str) = Eol::starts_with(right) {
-            (Some(eol), str)
+        let tabs = if self.meta.spaces == 0 {
+            str.starts_with('\t')
         } else {
-            (None, right)
+            self.meta.tabs
         };
+        let max = LineMeta::SPACES_MAX - self.meta.spaces;
+        let (spaces, str) = split_spaces(str, if tabs { b'\t' } else { b' ' }, max as usize);
 
-        // self.meta.eol = eol;
-        self.push_eol(eol);
-        str
-    }
+        if !spaces.is_empty() {
+            self.meta.spaces += spaces.len() as u8;
+            self.meta.tabs = tabs;
+        }
 
-    fn push_eol(&mut self, eol: Option<Eol>) {
-        self.meta.eol = eol;
-        self.advance();
+        str
     }
 
+    /// Ends the page, with the current record (if any) continued in the next
+    /// page.
     fn flush(&mut self) -> Page {
+        if !self.meta.is_empty() {
+            debug_assert!(self.meta.eol.is_none());
+            self.meta.continued = true;
+            self.advance();
+        }
+
         let page = std::mem::take(&mut self.page);
 
         self.index = 0;
-        self.page.offset.len += page.len as usize;
-        self.page.offset.chars += page.chars as usize;
-        self.page.offset.lines += page.lines as usize;
-
         page
     }
 
@@ -201,12 +270,17 @@ impl PageBuilder {
     }
 
     fn advance(&mut self) {
+        let start = self.index + self.meta.width();
+        let line = &self.page[start..][..self.meta.len as usize];
+        self.meta.ascii = line.iter().all(utils::is_ascii1);
         debug_assert!(self.meta.is_valid());
 
         let meta = &self.meta.serialize()[..self.meta.width()];
         self.page[self.index..][..meta.len()].copy_from_slice(meta);
 
         let spaces = self.meta.spaces as u16;
+        let line = &self.page[start..][..self.meta.len as usize];
+        let surrogates = page::surrogates(line) as u16;
         let (eol_len, eol_chars) = self
             .meta
             .eol
@@ -216,26 +290,26 @@ impl PageBuilder {
         self.index += self.meta.width() + self.meta.len as usize;
         self.page.len += spaces + self.meta.len + eol_len;
         self.page.chars += spaces + self.meta.chars + eol_chars;
-        self.page.lines += 1;
+        self.page.utf16 += spaces + self.meta.chars + surrogates + eol_chars;
+        self.page.lines += self.meta.eol.is_some() as u16;
         self.page.end = self.index as u16;
 
+        self.continued = self.meta.continued;
         self.meta = LineMeta::default();
     }
 
-    fn split<'a>(&self, str: &'a str) -> (&'a str, Option<Eol>, &'a str) {
+    fn ends_with_cr(&self) -> bool {
         let end = self.index + LineMeta::BYTES + self.meta.len as usize;
-        debug_assert!(end < page::BYTES);
 
-        let (left, right) = split_at(str, end);
-        let (line, eol) = Eol::split(left);
+        self.eols.contains(Eol::CRLF) && self.meta.len != 0 && self.page[end - 1] == b'\r'
+    }
 
-        if let Some((eol, _)) = eol {
-            (line, Some(eol), &str[line.len() + eol.as_bytes().len()..])
-        } else if let (Some(eol), str) = Eol::starts_with(right) {
-            (line, Some(eol), str)
-        } else {
-            (line, None, right)
-        }
+    fn is_line_start(&self) -> bool {
+        !self.continued && self.meta.len == 0
+    }
+
+    fn fits(&self, width: usize) -> bool {
+        self.index + width <= page::BYTES
     }
 }
 
@@ -249,11 +323,12 @@ fn split_at(str: &str, max: usize) -> (&str, &str) {
     (&str[..max], &str[max..])
 }
 
-fn split_spaces(str: &str, max: usize) -> (&str, &str) {
-    let i = str[..max.min(str.len())]
+fn split_spaces(str: &str, space: u8, max: usize) -> (&str, &str) {
+    let i = str
         .as_bytes()
         .iter()
-        .take_while(|&&b| b == b' ')
+        .take(max)
+        .take_while(|&&b| b == space)
         .count();
 
     (&str[..i], &str[i..])
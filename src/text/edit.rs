@@ -0,0 +1,188 @@
+use super::Edit;
+use super::PageBuilder;
+use super::TextIn;
+use crate::page;
+use crate::page::PointerKind;
+use crate::page::SharedPage;
+use crate::utils;
+use crate::Eol;
+use crate::Offset;
+use std::ops::Range;
+use std::ops::RangeBounds;
+
+impl<P: PointerKind> TextIn<P> {
+    /// Inserts `str` at byte `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds or not on a char boundary.
+    pub fn insert(&mut self, offset: usize, str: &str) {
+        self.replace_range(offset..offset, str);
+    }
+
+    /// Removes the bytes in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or not on char boundaries.
+    pub fn remove<R: RangeBounds<usize>>(&mut self, range: R) {
+        self.replace_range(range, "");
+    }
+
+    /// Replaces the bytes in `range` with `str`.
+    ///
+    /// Only the pages of the touched lines are rebuilt, other pages are shared
+    /// with previous clones of this text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or not on char boundaries.
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, str: &str) {
+        let range = utils::to_range(range, self.len).expect("range in bounds");
+        let (first, last) = self.touched(&range);
+
+        let (pages, region) = self.region(first, last);
+        let offset = region.start.len;
+        let mut string = String::with_capacity(region.end.len - offset + str.len());
+
+        for line in self.lines_range(first..=last).expect("valid lines") {
+            line.chunks().for_each(|chunk| string.push_str(chunk));
+        }
+        string.replace_range(range.start - offset..range.end - offset, str);
+
+        self.rebuild(pages, &string);
+    }
+
+    /// Applies `edits`, sorted and not overlapping, their ranges being in the
+    /// text before the edits.
+    ///
+    /// Edits in the same pages are rebuilt together, other pages are shared
+    /// with previous clones of this text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is out of bounds or not on char boundaries.
+    pub(crate) fn replace_ranges(&mut self, edits: &[Edit]) {
+        // Lines `first..=last` touched by edits `range`
+        let mut groups = Vec::<(usize, usize, Range<usize>)>::new();
+
+        for (i, edit) in edits.iter().enumerate() {
+            let (first, last) = self.touched(&edit.range);
+            let page = self.line_start(first).expect("valid line").0;
+
+            match groups.last_mut() {
+                Some((_, end, group)) if page <= self.line(*end).expect("valid line").end.0 => {
+                    *end = last.max(*end);
+                    group.end = i + 1;
+                }
+                _ => groups.push((first, last, i..i + 1)),
+            }
+        }
+
+        // Backwards, not to shift the pages and offsets of the next groups
+        for (first, last, group) in groups.into_iter().rev() {
+            let (pages, region) = self.region(first, last);
+            let offset = region.start.len;
+            let mut string = String::with_capacity(region.end.len - offset);
+
+            for line in self.lines_range(first..=last).expect("valid lines") {
+                line.chunks().for_each(|chunk| string.push_str(chunk));
+            }
+            for edit in edits[group].iter().rev() {
+                string.replace_range(
+                    edit.range.start - offset..edit.range.end - offset,
+                    &edit.str,
+                );
+            }
+
+            self.rebuild(pages, &string);
+        }
+    }
+
+    /// Returns the first and last lines touched by an edit of `range`.
+    fn touched(&self, range: &Range<usize>) -> (usize, usize) {
+        let start = self.seek(range.start, super::Metric::Len);
+        let end = self.seek(range.end, super::Metric::Len);
+        let (start, end) = start.zip(end).expect("range on char boundaries");
+
+        let mut first = start.lines;
+
+        // Keep `'\r'` and a potential `'\n'` together
+        if first != 0 && self.line(first - 1).and_then(|line| line.eol()) == Some(Eol::CR) {
+            first -= 1;
+        }
+
+        (first, end.lines)
+    }
+
+    /// Returns the pages and offsets of lines `first..=last`.
+    fn region(&self, first: usize, last: usize) -> (Range<(usize, usize)>, Range<Offset>) {
+        let (page, index, start) = self.line_start(first).expect("valid line");
+        let line = self.line(last).expect("valid line");
+
+        ((page, index)..line.end, start..line.next_offset())
+    }
+
+    /// Replaces the records in `pages` with `str`.
+    fn rebuild(&mut self, pages: Range<(usize, usize)>, str: &str) {
+        let (first, index) = pages.start;
+        let (last, end) = pages.end;
+
+        let continued = first != 0 && self.is_continued(first - 1);
+        let mut builder = PageBuilder::new(self.eols, continued);
+        let mut rebuilt = Vec::new();
+
+        // Records before
+        if let Some((_, page)) = self.pages.get(first) {
+            for record in page.records(0).take_while(|record| record.index < index) {
+                record
+                    .chunks()
+                    .for_each(|chunk| rebuilt.extend(builder.push(chunk)));
+            }
+        }
+
+        rebuilt.extend(builder.push(str));
+
+        // Records after
+        let mut next = first;
+        if let Some((_, page)) = self.pages.get(last) {
+            for record in page.records(end) {
+                record
+                    .chunks()
+                    .for_each(|chunk| rebuilt.extend(builder.push(chunk)));
+            }
+
+            next = last + 1;
+        }
+
+        // Rebuild up to the end of the last line, then merge with following
+        // pages while the last page is less than half full
+        while let Some((_, page)) = self.pages.get(next) {
+            let continued = next != 0 && self.is_continued(next - 1);
+
+            if !continued && builder.used() >= page::BYTES / 2 {
+                break;
+            }
+
+            page.chunks()
+                .for_each(|chunk| rebuilt.extend(builder.push(chunk)));
+            next += 1;
+        }
+
+        let page = builder.done();
+        if page.len != 0 {
+            rebuilt.push(page);
+        }
+
+        self.pages
+            .splice(first..next, rebuilt.into_iter().map(SharedPage::from));
+        self.set_offset(self.pages.size());
+    }
+
+    /// Returns `true` if the last record of `page` is continued.
+    fn is_continued(&self, page: usize) -> bool {
+        self.pages
+            .get(page)
+            .is_some_and(|(_, page)| page.is_continued())
+    }
+}
@@ -0,0 +1,180 @@
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::Eol;
+use crate::LineMeta;
+
+/// Counts of the line endings of a [`Text`], per [`Eol`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct EolStats {
+    counts: [usize; Eol::ALL.len()],
+}
+
+impl EolStats {
+    /// Returns the number of `eol`s.
+    pub fn count(&self, eol: Eol) -> usize {
+        self.counts[eol as usize]
+    }
+
+    /// Returns the total number of eols.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Returns `true` if there are different eols.
+    pub fn is_mixed(&self) -> bool {
+        self.iter().nth(1).is_some()
+    }
+
+    /// Counts an `eol`.
+    pub(crate) fn add(&mut self, eol: Eol) {
+        self.counts[eol as usize] += 1;
+    }
+
+    /// Returns the most common eol (the first in [`Eol::ALL`] on ties),
+    /// or `None` if there are none.
+    pub fn dominant(&self) -> Option<Eol> {
+        self.iter()
+            .fold(None, |max: Option<(Eol, usize)>, (eol, count)| match max {
+                Some((_, max_count)) if max_count >= count => max,
+                _ => Some((eol, count)),
+            })
+            .map(|(eol, _)| eol)
+    }
+
+    /// Returns an iterator over the eols (in [`Eol::ALL`] order) and their
+    /// (non-zero) counts.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (Eol, usize)> {
+        Eol::ALL
+            .into_iter()
+            .map(|eol| (eol, self.count(eol)))
+            .filter(|&(_, count)| count != 0)
+    }
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the counts of line endings.
+    pub fn eol_stats(&self) -> EolStats {
+        let mut stats = EolStats::default();
+
+        for (_, page) in &self.pages {
+            for eol in page.records(0).filter_map(|record| record.meta.eol) {
+                stats.add(eol);
+            }
+        }
+
+        stats
+    }
+
+    /// Returns the most common line ending (see [`EolStats::dominant`]).
+    pub fn dominant_eol(&self) -> Option<Eol> {
+        self.eol_stats().dominant()
+    }
+
+    /// Replaces all line endings with `eol`.
+    ///
+    /// Only the [`LineMeta`]s are rewritten, without touching content bytes
+    /// nor rebuilding pages, but for lines whose new eol merges with a
+    /// neighbouring `'\r'` or `'\n'` (into a `"\r\n"`): those are rebuilt,
+    /// to be read as a fresh build of the text would read them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `eol` is not in [`Self::eols`].
+    pub fn normalize_eols(&mut self, eol: Eol) {
+        assert!(self.eols.contains(eol), "eol not recognized");
+
+        let merged = self.merged_lines(eol);
+        let pages = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, page))| page.records(0).any(|record| is_other(record.meta, eol)))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        for page in pages {
+            self.pages.update(page, |page| {
+                let records = page
+                    .records(0)
+                    .filter(|record| is_other(record.meta, eol))
+                    .map(|record| (record.index, record.meta))
+                    .collect::<Vec<_>>();
+                let bytes = page.bytes.make_mut();
+
+                for (index, meta) in records {
+                    let before = meta.eol.expect("eol");
+                    let meta = LineMeta {
+                        eol: Some(eol),
+                        ..meta
+                    };
+                    let width = meta.width();
+                    bytes[index..][..width].copy_from_slice(&meta.serialize()[..width]);
+
+                    let (before_len, after_len) = (before.as_str().len(), eol.as_str().len());
+                    let (before_chars, after_chars) =
+                        (before.as_chars().len(), eol.as_chars().len());
+                    page.len = page.len - before_len as u16 + after_len as u16;
+                    page.chars = page.chars - before_chars as u16 + after_chars as u16;
+                    page.utf16 = page.utf16 - before_chars as u16 + after_chars as u16;
+                }
+            });
+        }
+
+        self.set_offset(self.pages.size());
+        self.file_eol = Some(eol);
+
+        // The bytes are right, but not how they read
+        for line in merged {
+            let end = self.line(line).expect("valid line").next_offset().len;
+            self.replace_range(end - eol.as_str().len()..end, eol.as_str());
+        }
+    }
+
+    /// Returns the lines whose eol, replaced with `eol`, would merge into a
+    /// CRLF with a `'\r'` ending their content, or a `'\n'` starting the
+    /// next line.
+    fn merged_lines(&self, eol: Eol) -> Vec<usize> {
+        // Content `'\r'`s and `'\n'`s are only eols when not recognized
+        let other = match eol {
+            Eol::LF => Eol::CR,
+            Eol::CR => Eol::LF,
+            _ => return Vec::new(),
+        };
+        if !self.eols.contains(Eol::CRLF) || self.eols.contains(other) {
+            return Vec::new();
+        }
+
+        let mut lines = self.lines().peekable();
+        let mut merged = Vec::new();
+
+        while let Some(line) = lines.next() {
+            if line.eol().is_none_or(|old| old == eol) {
+                continue;
+            }
+
+            let merges = if eol == Eol::LF {
+                line.content().last().is_some_and(|str| str.ends_with('\r'))
+            } else {
+                lines.peek().is_some_and(|next| {
+                    next.spaces() == 0
+                        && next
+                            .content()
+                            .next()
+                            .is_some_and(|str| str.starts_with('\n'))
+                })
+            };
+
+            if merges {
+                merged.push(line.index());
+            }
+        }
+
+        merged
+    }
+}
+
+/// Returns `true` if `meta` has an eol other than `eol`.
+fn is_other(meta: LineMeta, eol: Eol) -> bool {
+    meta.eol.is_some_and(|other| other != eol)
+}
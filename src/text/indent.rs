@@ -0,0 +1,234 @@
+use super::LineRef;
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::utils;
+use crate::LineMeta;
+use std::collections::HashMap;
+use std::ops::RangeBounds;
+
+/// The leading indentation of a line.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Indent {
+    /// `n` spaces.
+    Spaces(usize),
+    /// `n` tabs.
+    Tabs(usize),
+}
+
+impl Indent {
+    /// Returns the number of spaces or tabs.
+    pub fn len(&self) -> usize {
+        match *self {
+            Self::Spaces(len) | Self::Tabs(len) => len,
+        }
+    }
+
+    /// Returns `true` if there is no indentation.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` for tabs.
+    pub fn is_tabs(&self) -> bool {
+        matches!(self, Self::Tabs(_))
+    }
+
+    /// Returns the indentation char.
+    pub fn as_char(&self) -> char {
+        if self.is_tabs() {
+            '\t'
+        } else {
+            ' '
+        }
+    }
+}
+
+/// The indentation style of a [`Text`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum IndentStyle {
+    /// Spaces, by levels of `width`.
+    Spaces(usize),
+    /// Tabs.
+    Tabs,
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the indentation of line `line`, or `None` if `line >
+    /// self.lines`.
+    ///
+    /// A mixed indentation (e.g. tabs then spaces) only counts its first run.
+    pub fn indent_of(&self, line: usize) -> Option<Indent> {
+        self.line(line).map(|line| indent(&line))
+    }
+
+    /// Guesses the indentation style, from the indentation of lines,
+    /// or `None` if no line is indented.
+    ///
+    /// The width of spaces is the most common difference between the
+    /// indentation of consecutive (non blank) lines.
+    pub fn detect_indent_style(&self) -> Option<IndentStyle> {
+        let mut tabs = 0;
+        let mut spaces = 0;
+        let mut widths = HashMap::<usize, usize>::new();
+        let mut previous = 0;
+
+        for line in self.lines().filter(|line| line.content().next().is_some()) {
+            match indent(&line) {
+                Indent::Tabs(_) => tabs += 1,
+                Indent::Spaces(len) => {
+                    spaces += (len != 0) as usize;
+
+                    if len != previous {
+                        *widths.entry(len.abs_diff(previous)).or_default() += 1;
+                        previous = len;
+                    }
+                }
+            }
+        }
+
+        if tabs == 0 && spaces == 0 {
+            None
+        } else if tabs > spaces {
+            Some(IndentStyle::Tabs)
+        } else {
+            widths
+                .into_iter()
+                .max_by_key(|&(width, count)| (count, std::cmp::Reverse(width)))
+                .map(|(width, _)| IndentStyle::Spaces(width))
+        }
+    }
+
+    /// Indents (or dedents, if negative) lines in `range` by `delta` spaces
+    /// or tabs, keeping the kind of each line's indentation.
+    ///
+    /// Unindented lines are indented with tabs if `style` is
+    /// [`IndentStyle::Tabs`] (e.g. as guessed by
+    /// [`Self::detect_indent_style`]), with spaces otherwise. Blank lines
+    /// are not indented.
+    ///
+    /// When possible, only the [`LineMeta`]s are rewritten, without touching
+    /// content bytes nor rebuilding pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of `0..=self.lines`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::Text;
+    /// let mut text = Text::from("fn main() {\n\tx();\n}\ny();\n");
+    ///
+    /// text.reindent(3..4, 1, text.detect_indent_style());
+    /// assert!(text.to_string() == "fn main() {\n\tx();\n}\n\ty();\n");
+    /// ```
+    pub fn reindent<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        delta: isize,
+        style: Option<IndentStyle>,
+    ) {
+        let range = utils::to_range(range, self.lines + 1).expect("range in bounds");
+        let tabs = style == Some(IndentStyle::Tabs);
+
+        for line in range {
+            let line = self.line(line).expect("valid line");
+            let before = indent(&line);
+            let (page, index, start) = (line.page, line.index, line.offset());
+
+            if delta > 0 && line.indent().len() + line.len() == before.len() {
+                continue; // Blank line
+            }
+
+            let len = before.len().saturating_add_signed(delta);
+            let after = match before {
+                Indent::Tabs(_) => Indent::Tabs(len),
+                Indent::Spaces(0) if tabs => Indent::Tabs(len),
+                Indent::Spaces(_) => Indent::Spaces(len),
+            };
+
+            // Same width (e.g. no spaces to no tabs)
+            if before.len() == after.len() {
+                continue;
+            }
+
+            if !self.set_indent(page, index, before, after) {
+                let indent = after.as_char().to_string().repeat(after.len());
+
+                self.replace_range(start..start + before.len(), &indent);
+            }
+        }
+    }
+
+    /// Rewrites the [`LineMeta`] of the line starting at byte `index` in
+    /// `page` (or in the next page, at the end of `page`) from `before` to
+    /// `after`, returning `false` if not possible in place.
+    fn set_indent(&mut self, page: usize, index: usize, before: Indent, after: Indent) -> bool {
+        let (page, record) = self.records(page, index).next().expect("line has a record");
+        let (index, ascii) = (
+            record.index,
+            record.str.bytes().next().is_none_or(|b| b.is_ascii()),
+        );
+        let record = record.meta;
+
+        // Leading spaces overflowing in the content, or to keep with a
+        // non-ASCII char (see `PageBuilder`)
+        if before.len() != record.spaces as usize || !ascii {
+            return false;
+        }
+
+        let meta = LineMeta {
+            spaces: after.len() as u8,
+            tabs: after.is_tabs() && !after.is_empty(),
+            ..record
+        };
+
+        // Too many spaces, or a different width
+        if after.len() > LineMeta::SPACES_MAX as usize || meta.width() != record.width() {
+            return false;
+        }
+
+        self.pages.update(page, |page| {
+            let bytes = page.bytes.make_mut();
+            bytes[index..][..LineMeta::BYTES].copy_from_slice(&meta.serialize());
+
+            let (before, after) = (before.len() as u16, after.len() as u16);
+            page.len = page.len - before + after;
+            page.chars = page.chars - before + after;
+            page.utf16 = page.utf16 - before + after;
+        });
+        self.set_offset(self.pages.size());
+
+        true
+    }
+}
+
+/// Returns the indentation of `line`: leading spaces (or tabs), and the
+/// following ones in the content.
+fn indent<P: PointerKind>(line: &LineRef<P>) -> Indent {
+    let compressed = line.indent();
+    let char = match compressed.chars().next() {
+        Some(char) => char,
+        None => match line.content().next().and_then(|str| str.chars().next()) {
+            Some(char @ (' ' | '\t')) => char,
+            _ => return Indent::Spaces(0),
+        },
+    };
+
+    let mut len = compressed.len();
+    for str in line.content() {
+        let count = str.bytes().take_while(|&byte| byte == char as u8).count();
+        len += count;
+
+        if count != str.len() {
+            break;
+        }
+    }
+
+    if char == '\t' {
+        Indent::Tabs(len)
+    } else {
+        Indent::Spaces(len)
+    }
+}
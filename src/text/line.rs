@@ -0,0 +1,242 @@
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::page::RcKind;
+use crate::page::Record;
+use crate::Eol;
+use crate::Offset;
+use crate::SPACES;
+use crate::TABS;
+
+/// A line of a [`Text`], borrowed from its pages.
+///
+/// A line is made of one or more [`Record`]s, possibly spanning several pages.
+#[derive(Copy, Clone, Debug)]
+pub struct LineRef<'a, P: PointerKind = RcKind> {
+    pub(super) text:  &'a TextIn<P>,
+    /// Page of the first record.
+    pub(super) page:  usize,
+    /// Byte index of the first record in `page`.
+    pub(super) index: usize,
+    /// Page and byte index following the last record.
+    pub(super) end:   (usize, usize),
+    /// Offset of the line in the text.
+    offset:           Offset,
+    /// Size of the line (`lines` being the number of eols).
+    size:             Offset,
+    /// Number of leading spaces (or tabs).
+    spaces:           u8,
+    tabs:             bool,
+    eol:              Option<Eol>,
+    /// Whether the content is all printable ASCII (see [`LineMeta`]).
+    ///
+    /// [`LineMeta`]: crate::LineMeta
+    pub(super) ascii: bool,
+}
+
+impl<'a, P: PointerKind> LineRef<'a, P> {
+    /// Creates the line starting at byte `index` in `page`, at `offset`.
+    pub(crate) fn new(text: &'a TextIn<P>, page: usize, index: usize, offset: Offset) -> Self {
+        let mut line = Self {
+            text,
+            page,
+            index,
+            end: (page, index),
+            offset,
+            size: Offset::default(),
+            spaces: 0,
+            tabs: false,
+            eol: None,
+            ascii: true,
+        };
+
+        for (i, (page, record)) in text.records(page, index).enumerate() {
+            if i == 0 {
+                line.spaces = record.meta.spaces;
+                line.tabs = record.meta.tabs;
+            } else {
+                debug_assert!(record.meta.spaces == 0);
+            }
+
+            line.ascii &= record.meta.ascii;
+            line.size += record.offset();
+            line.eol = record.meta.eol;
+            line.end = (page, record.end());
+
+            if !record.meta.continued {
+                break;
+            }
+        }
+
+        line
+    }
+
+    /// Returns the (0-based) index of this line.
+    pub fn index(&self) -> usize {
+        self.offset.lines
+    }
+
+    /// Returns the byte offset of this line in the text.
+    pub fn offset(&self) -> usize {
+        self.offset.len
+    }
+
+    /// Returns the char offset of this line in the text.
+    pub fn char_offset(&self) -> usize {
+        self.offset.chars
+    }
+
+    /// Returns the UTF-16 code unit offset of this line in the text.
+    pub fn utf16_offset(&self) -> usize {
+        self.offset.utf16
+    }
+
+    /// Returns the leading spaces (or tabs).
+    pub fn indent(&self) -> &'a str {
+        let indent = if self.tabs { TABS } else { SPACES };
+        &indent[..self.spaces as usize]
+    }
+
+    /// Returns the number of leading spaces (`0` if indented with tabs).
+    pub fn spaces(&self) -> u8 {
+        if self.tabs {
+            0
+        } else {
+            self.spaces
+        }
+    }
+
+    /// Returns the number of leading tabs (`0` if indented with spaces).
+    pub fn tabs(&self) -> u8 {
+        if self.tabs {
+            self.spaces
+        } else {
+            0
+        }
+    }
+
+    /// Returns the len (in bytes) of the content,
+    /// without leading spaces (or tabs) nor eol.
+    pub fn len(&self) -> usize {
+        self.size.len - self.spaces as usize - self.eol.map_or(0, |eol| eol.as_str().len())
+    }
+
+    /// Returns the len (in chars) of the content,
+    /// without leading spaces (or tabs) nor eol.
+    pub fn chars(&self) -> usize {
+        self.size.chars - self.spaces as usize - self.eol_chars()
+    }
+
+    /// Returns the len (in UTF-16 code units) of the content,
+    /// without leading spaces (or tabs) nor eol.
+    pub fn utf16(&self) -> usize {
+        self.size.utf16 - self.spaces as usize - self.eol_chars()
+    }
+
+    /// Returns the line ending, if any.
+    pub fn eol(&self) -> Option<Eol> {
+        self.eol
+    }
+
+    /// Returns `true` if the line has no leading spaces (or tabs), content nor
+    /// eol.
+    pub fn is_empty(&self) -> bool {
+        self.size.len == 0
+    }
+
+    /// Returns the content as a single `&str`,
+    /// if it is not split across records.
+    pub fn as_str(&self) -> Option<&'a str> {
+        let mut content = self.content();
+
+        match (content.next(), content.next()) {
+            (None, _) => Some(""),
+            (Some(str), None) => Some(str),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the `&str` chunks of the content,
+    /// without leading spaces (or tabs) nor eol.
+    pub fn content(&self) -> impl 'a + Iterator<Item = &'a str> {
+        self.records()
+            .map(|(_, record)| record.str)
+            .filter(|str| !str.is_empty())
+    }
+
+    /// Returns an iterator over the `&str` chunks of this line:
+    /// leading spaces (or tabs), content and eol.
+    pub fn chunks(&self) -> impl 'a + Iterator<Item = &'a str> {
+        let spaces = (self.spaces != 0).then(|| self.indent());
+        let eol = self.eol.map(|eol| eol.as_str());
+
+        spaces.into_iter().chain(self.content()).chain(eol)
+    }
+
+    /// Returns an iterator over the records of this line, with their page.
+    pub fn records(&self) -> impl 'a + Iterator<Item = (usize, Record<'a>)> {
+        let end = self.end;
+
+        self.text
+            .records(self.page, self.index)
+            .take_while(move |&(page, record)| (page, record.index) < end)
+    }
+
+    /// Returns the offset of the line following this one.
+    pub(crate) fn next_offset(&self) -> Offset {
+        let mut offset = self.offset;
+        offset += self.size;
+        offset.lines = self.offset.lines + 1;
+        offset
+    }
+
+    fn eol_chars(&self) -> usize {
+        self.eol.map_or(0, |eol| eol.as_chars().len())
+    }
+}
+
+/// An iterator over [`LineRef`]s, returned from [`Text::lines`] and
+/// [`Text::lines_range`].
+#[derive(Clone, Debug)]
+pub struct Lines<'a, P: PointerKind = RcKind> {
+    text:   &'a TextIn<P>,
+    page:   usize,
+    index:  usize,
+    offset: Offset,
+    end:    usize,
+}
+
+impl<'a, P: PointerKind> Lines<'a, P> {
+    pub(crate) fn new(text: &'a TextIn<P>, line: LineRef<'a, P>, end: usize) -> Self {
+        Self {
+            text,
+            page: line.page,
+            index: line.index,
+            offset: line.offset,
+            end,
+        }
+    }
+}
+
+impl<'a, P: PointerKind> Iterator for Lines<'a, P> {
+    type Item = LineRef<'a, P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset.lines >= self.end {
+            return None;
+        }
+
+        let line = LineRef::new(self.text, self.page, self.index, self.offset);
+        (self.page, self.index) = line.end;
+        self.offset = line.next_offset();
+
+        Some(line)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end.saturating_sub(self.offset.lines);
+        (len, Some(len))
+    }
+}
+
+impl<'a, P: PointerKind> ExactSizeIterator for Lines<'a, P> {}
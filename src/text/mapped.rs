@@ -0,0 +1,244 @@
+use super::ReadError;
+use super::Text;
+use super::TextBuilder;
+use crate::eol::EolSet;
+use crate::utils;
+use crate::utils::Mmap;
+use std::cell::OnceCell;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use std::str::from_utf8;
+
+/// Len (in bytes, about) of the segments built at once.
+///
+/// Segments are only 64 bytes long in tests, to exercise segment boundaries.
+#[cfg(not(test))]
+const SEGMENT: usize = 64 * 1024;
+#[cfg(test)]
+const SEGMENT: usize = 64;
+
+/// Len (in bytes) from which files are validated in parallel.
+#[cfg(not(test))]
+const PARALLEL: usize = 4 * 1024 * 1024;
+#[cfg(test)]
+const PARALLEL: usize = 256;
+
+/// A memory-mapped file, built into [`Text`]s lazily.
+///
+/// Opening only validates the file (UTF-8, in parallel slices) and splits it
+/// in segments at eols: the pages of a segment are built on first access,
+/// so memory only grows with the segments actually viewed.
+///
+/// The file must not be modified while mapped (see [`Self::open_with`]).
+///
+/// # Usage
+///
+/// ```no_run
+/// # use grappe::text::MappedText;
+/// // Safety: the file is not modified while mapped
+/// let file = unsafe { MappedText::open("huge.log")? };
+/// assert!(file.built() == 0);
+///
+/// // Builds only the segment of byte 1_000_000
+/// let (index, text) = file.segment_at(1_000_000).unwrap();
+/// let range = file.range(index).unwrap();
+/// println!("{:?}: {} lines", range, text.lines + 1);
+/// assert!(file.built() == 1);
+/// # Ok::<(), grappe::text::ReadError>(())
+/// ```
+#[derive(Debug)]
+pub struct MappedText {
+    map:      Mmap,
+    eols:     EolSet,
+    /// Start of the segments.
+    starts:   Vec<usize>,
+    segments: Vec<OnceCell<Text>>,
+}
+
+impl MappedText {
+    /// Maps the file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// See [`Self::open_with`].
+    pub unsafe fn open<T: AsRef<Path>>(path: T) -> Result<Self, ReadError> {
+        Self::open_with(path, EolSet::ALL)
+    }
+
+    /// Maps the file at `path`, recognizing only `eols`.
+    ///
+    /// Fails on I/O errors or invalid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified (by this process or others) until the
+    /// returned `MappedText` is dropped: validated bytes could become invalid
+    /// UTF-8 under `&str`s, and truncating the file (e.g. rotating logs with
+    /// `copytruncate`) raises `SIGBUS` on access.
+    pub unsafe fn open_with<T: AsRef<Path>>(path: T, eols: EolSet) -> Result<Self, ReadError> {
+        let map = Mmap::map(&File::open(path)?)?;
+
+        validate(&map)?;
+
+        let starts = starts(unsafe { utf8!(&map) }, eols);
+        let segments = starts.iter().map(|_| OnceCell::new()).collect();
+
+        Ok(Self {
+            map,
+            eols,
+            starts,
+            segments,
+        })
+    }
+
+    /// Returns the len, in bytes.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the whole file.
+    pub fn as_str(&self) -> &str {
+        unsafe { utf8!(&self.map) }
+    }
+
+    /// Returns the number of segments.
+    pub fn segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns the number of segments built so far.
+    pub fn built(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.get().is_some())
+            .count()
+    }
+
+    /// Returns the byte range of segment `index`, or `None` if out of bounds.
+    ///
+    /// Segments start after an eol (or at the start of the file).
+    pub fn range(&self, index: usize) -> Option<Range<usize>> {
+        let start = *self.starts.get(index)?;
+        let end = self.starts.get(index + 1).copied().unwrap_or(self.len());
+
+        Some(start..end)
+    }
+
+    /// Returns segment `index`, built on first access, or `None` if out of
+    /// bounds.
+    pub fn segment(&self, index: usize) -> Option<&Text> {
+        let range = self.range(index)?;
+
+        Some(self.segments[index].get_or_init(|| {
+            let mut builder = TextBuilder::with_eols(self.eols);
+            builder.push(&self.as_str()[range]);
+            builder.done()
+        }))
+    }
+
+    /// Returns the index of the segment containing byte `offset` and this
+    /// segment, or `None` if out of bounds.
+    pub fn segment_at(&self, offset: usize) -> Option<(usize, &Text)> {
+        if offset >= self.len() {
+            return None;
+        }
+
+        let index = self.starts.partition_point(|&start| start <= offset) - 1;
+        self.segment(index).map(|text| (index, text))
+    }
+
+    /// Returns the whole file as a [`Text`], building all segments.
+    ///
+    /// Pages are shared with the segments.
+    pub fn to_text(&self) -> Text {
+        let mut text = Text {
+            eols: self.eols,
+            ..Default::default()
+        };
+
+        for index in 0..self.segments() {
+            for (_, page) in &self.segment(index).expect("valid segment").pages {
+                text.push(page.clone());
+            }
+        }
+
+        text.file_eol = text.dominant_eol();
+        text
+    }
+}
+
+/// Validates `bytes` as UTF-8, in parallel slices above [`PARALLEL`] bytes.
+fn validate(bytes: &[u8]) -> Result<(), ReadError> {
+    let threads = if bytes.len() < PARALLEL {
+        1
+    } else {
+        std::thread::available_parallelism().map_or(1, |threads| threads.get())
+    };
+
+    // Slices start on char boundaries (in valid UTF-8)
+    let size = bytes.len() / threads + 1;
+    let mut starts = (0..threads)
+        .map(|i| {
+            let start = (i * size).min(bytes.len());
+            (start..bytes.len().min(start + 3))
+                .find(|&i| !utils::is_continuation(bytes[i]))
+                .unwrap_or(bytes.len().min(start + 3))
+        })
+        .collect::<Vec<_>>();
+    starts.push(bytes.len());
+
+    let error = std::thread::scope(|scope| {
+        let handles = starts
+            .windows(2)
+            .map(|range| {
+                let (start, end) = (range[0], range[1]);
+                scope.spawn(move || {
+                    from_utf8(&bytes[start..end])
+                        .err()
+                        .map(|err| start + err.valid_up_to())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("validation does not panic"))
+            .min()
+    });
+
+    match error {
+        Some(offset) => Err(ReadError::Utf8 { offset }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the starts of the segments of `str`, after the first eol (of
+/// `eols`) following every [`SEGMENT`] bytes.
+fn starts(str: &str, eols: EolSet) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut at = 0;
+
+    while at + SEGMENT < str.len() {
+        let mut from = at + SEGMENT;
+        while !str.is_char_boundary(from) {
+            from += 1;
+        }
+
+        match eols.split(&str[from..]) {
+            (line, Some((eol, _))) => at = from + line.len() + eol.as_str().len(),
+            (_, None) => break,
+        }
+
+        if at < str.len() {
+            starts.push(at);
+        }
+    }
+
+    starts
+}
@@ -1,110 +1,302 @@
 mod builder;
+mod edit;
+mod eols;
+mod indent;
 // mod from_bytes;
 // mod splitter;
+mod line;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod motion;
+mod pages;
+mod position;
 mod reader;
+#[cfg(feature = "regex")]
+mod regex;
+mod save;
+mod search;
+mod sync;
+mod tree;
+mod undo;
+mod width;
+mod wrap;
+mod write;
 
 pub use builder::*;
+pub use eols::*;
+pub use indent::*;
 // pub use splitter::*;
+pub use line::*;
+#[cfg(feature = "mmap")]
+pub use mapped::*;
+pub use pages::Pages;
+pub use position::*;
 pub use reader::*;
+#[cfg(feature = "regex")]
+pub use regex::*;
+pub use save::*;
+pub use search::*;
+pub use sync::*;
+pub use undo::*;
+pub use width::*;
+pub use wrap::*;
+pub use write::*;
+
+#[cfg(test)]
+mod tests;
 
 use super::Eol;
+use crate::cluster::Segmenter;
+use crate::eol::EolSet;
 use crate::page;
 use crate::page::*;
+use crate::utils;
+use crate::Cluster;
+use crate::Offset;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
-use std::io;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::io::Read;
+use std::ops::RangeBounds;
 use std::path::Path;
-use std::str::FromStr;
 
+/// A text, stored in [`Page`]s.
+///
+/// Pages and the nodes of their tree are shared behind `P` pointers: see
+/// [`Text`] (with [`Rc`](std::rc::Rc)s) and [`SyncText`] (with
+/// [`Arc`](std::sync::Arc)s), which have the same API.
 #[derive(Clone, Default, Debug)]
-pub struct Text {
-    pub len:   usize,
-    pub chars: usize,
-    pub lines: usize,
-    pub pages: Vec<RcPage>,
+pub struct TextIn<P: PointerKind = RcKind> {
+    /// Len, in bytes.
+    pub len:      usize,
+    /// Len, in chars.
+    pub chars:    usize,
+    /// Len, in UTF-16 code units.
+    pub utf16:    usize,
+    /// Number of eols (the text has `lines + 1` lines, the last one maybe
+    /// empty).
+    pub lines:    usize,
+    pub pages:    Pages<P>,
+    /// Recognized eols (all by default).
+    pub eols:     EolSet,
+    /// Eol of the file: the dominant eol when read (see [`Self::read`]), or
+    /// the eol normalized to (see [`Self::normalize_eols`]). Saved lines get
+    /// it (see [`SaveOptions::dominant_eol`]).
+    pub file_eol: Option<Eol>,
 }
 
-impl Text {
-    // Max len of line (without counting leading spaces and eol)
-    pub const LINE_MAX_LEN: usize = page::BYTES - 3;
+/// A [`TextIn`] stored in [`Rc`](std::rc::Rc)s.
+pub type Text = TextIn<RcKind>;
 
-    /*
-    pub fn open<T: AsRef<Path>>(file: T) -> io::Result<Result<Self, LineError>> {
-        Self::read(BufReader::new(File::open(file)?))
+impl<P: PointerKind> TextIn<P> {
+    /// Reads the file at `path`.
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<Self, ReadError> {
+        Self::open_with(path, EolSet::ALL)
     }
 
-    pub fn read<T: BufRead>(mut reader: T) -> io::Result<Result<Self, LineError>> {
-        let mut splitter = Splitter::default();
-        let mut builder = TextBuilder::default();
-
-        loop {
-            let buf = reader.fill_buf()?;
-            let len = buf.len();
+    /// Reads the file at `path`, recognizing only `eols`.
+    pub fn open_with<T: AsRef<Path>>(path: T, eols: EolSet) -> Result<Self, ReadError> {
+        Self::read_with(File::open(path)?, eols)
+    }
 
-            if buf.is_empty() {
-                break;
-            }
+    /// Reads `reader` to the end.
+    ///
+    /// Fails on I/O errors or invalid UTF-8.
+    pub fn read<T: Read>(reader: T) -> Result<Self, ReadError> {
+        Self::read_with(reader, EolSet::ALL)
+    }
 
-            if let Err(err) = splitter.feed(buf, |split| builder.push(split)) {
-                return Ok(Err(err));
-            }
+    /// Reads `reader` to the end, recognizing only `eols`.
+    ///
+    /// Fails on I/O errors or invalid UTF-8.
+    pub fn read_with<T: Read>(reader: T, eols: EolSet) -> Result<Self, ReadError> {
+        let mut reader = Reader::new(reader);
+        let mut builder = TextBuilderIn::with_eols(eols);
 
-            reader.consume(len);
+        while let Some(str) = reader.read()? {
+            builder.push(str);
         }
 
-        if let Err(err) = splitter.done(|split| builder.push(split)) {
-            return Ok(Err(err));
-        }
+        let mut text = builder.done();
+        text.file_eol = text.dominant_eol();
+        Ok(text)
+    }
 
-        Ok(Ok(builder.done()))
+    /// Returns a snapshot of this text.
+    ///
+    /// Pages are shared (this only copies the root of the page tree), and
+    /// edits on either text never affect the other.
+    pub fn snapshot(&self) -> Self {
+        self.clone()
     }
-    */
 
-    // pub fn push(&mut self, mut page: RcPage) {
-    // page.offset.len = self.len;
-    // page.offset.lines = self.lines;
-    //
-    // self.len += page.len as usize;
-    // self.lines += page.lines as usize;
-    //
-    // self.pages.push(page);
-    // }
-}
+    /// Returns the line at (0-based) index `line`,
+    /// or `None` if `line > self.lines`.
+    pub fn line(&self, line: usize) -> Option<LineRef<'_, P>> {
+        let (page, index, offset) = self.line_start(line)?;
 
-/*
-impl FromStr for Text {
-    type Err = LineError;
+        Some(LineRef::new(self, page, index, offset))
+    }
 
-    fn from_str(str: &str) -> Result<Self, Self::Err> {
-        let mut splitter = Splitter::default();
-        let mut builder = TextBuilder::default();
+    /// Returns an iterator over the lines.
+    pub fn lines(&self) -> Lines<'_, P> {
+        self.lines_range(..).expect("valid range")
+    }
 
-        splitter.feed(str.as_bytes(), |split| builder.push(split))?;
-        splitter.done(|split| builder.push(split))?;
+    /// Returns an iterator over the lines in `range`,
+    /// or `None` if `range` is out of `0..=self.lines`.
+    pub fn lines_range<R: RangeBounds<usize>>(&self, range: R) -> Option<Lines<'_, P>> {
+        let range = utils::to_range(range, self.lines + 1)?;
+        let line = self.line(range.start)?;
 
-        Ok(builder.done())
+        Some(Lines::new(self, line, range.end))
     }
-}
 
-impl ToString for Text {
-    fn to_string(&self) -> String {
-        let mut string = String::with_capacity(self.len);
+    /// Returns an iterator over the grapheme clusters, with their byte
+    /// offset.
+    ///
+    /// Clusters are borrowed from the pages, but for the (rare) ones
+    /// straddling chunks (e.g. leading spaces and a combining mark, or long
+    /// lines continued across records), see [`Segmenter`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::Text;
+    /// let text = Text::from("  e\u{301}\r\n🐈");
+    /// let clusters = text.clusters().collect::<Vec<_>>();
+    ///
+    /// assert!(clusters.len() == 5);
+    /// assert!(clusters[2] == (2, "e\u{301}".into()));
+    /// assert!(clusters[3] == (5, "\r\n".into()));
+    /// ```
+    pub fn clusters(&self) -> impl '_ + Iterator<Item = (usize, Cow<'_, str>)> {
+        let mut chunks = self.pages.iter().flat_map(|(_, page)| page.chunks());
+        let mut segmenter = Segmenter::default();
+        let mut clusters = VecDeque::new();
+        // Current chunk, with its offset
+        let mut chunk = (0, "");
 
-        for page in &self.pages {
-            let mut bytes = &page[..];
+        std::iter::from_fn(move || loop {
+            if let Some(cluster) = clusters.pop_front() {
+                return Some(cluster);
+            }
 
-            for _ in (0..page.lines).enumerate() {
-                let (meta, str, next_bytes) = LineMeta::deserialize(bytes);
-                bytes = next_bytes;
+            match chunks.next() {
+                Some(str) => {
+                    chunk = (chunk.0 + chunk.1.len(), str);
 
-                (0..meta.spaces).for_each(|_| string.push(' '));
-                string.push_str(str);
-                meta.eol.map(|eol| string.push_str(eol.as_str()));
+                    for (i, cluster) in segmenter.segment(str) {
+                        clusters.push_back(borrow(chunk, i, cluster));
+                    }
+                }
+                None => {
+                    let (i, cluster) = segmenter.done()?;
+                    return Some(borrow(chunk, i, cluster));
+                }
             }
+        })
+    }
+
+    /// Returns the page, byte index in page and offset of the start of line
+    /// `line`, or `None` if `line > self.lines`.
+    pub(crate) fn line_start(&self, line: usize) -> Option<(usize, usize, Offset)> {
+        if line > self.lines {
+            return None;
         }
 
-        string
+        let page = self.pages.partition_point(|end| end.lines < line);
+
+        let (index, offset) = if let Some((mut offset, page)) = self.pages.get(page) {
+            let mut index = 0;
+
+            for record in page.records(0) {
+                if offset.lines == line {
+                    break;
+                }
+
+                index = record.end();
+                offset += record.offset();
+            }
+
+            (index, offset)
+        } else {
+            (0, self.offset())
+        };
+
+        debug_assert!(offset.lines == line);
+        Some((page, index, offset))
+    }
+
+    /// Sets `len`, `chars`, `utf16` and `lines` from `offset`.
+    pub(crate) fn set_offset(&mut self, offset: Offset) {
+        self.len = offset.len;
+        self.chars = offset.chars;
+        self.utf16 = offset.utf16;
+        self.lines = offset.lines;
+    }
+
+    /// Returns the offset of the end of the text.
+    pub(crate) fn offset(&self) -> Offset {
+        Offset {
+            len:   self.len,
+            chars: self.chars,
+            utf16: self.utf16,
+            lines: self.lines,
+        }
+    }
+
+    /// Returns an iterator over the records (with their page index),
+    /// starting at byte `index` in `page`.
+    pub(crate) fn records(
+        &self,
+        page: usize,
+        index: usize,
+    ) -> impl '_ + Iterator<Item = (usize, Record<'_>)> {
+        self.pages
+            .iter_from(page)
+            .enumerate()
+            .flat_map(move |(i, (_, p))| {
+                let start = if i == 0 { index } else { 0 };
+                p.records(start).map(move |record| (page + i, record))
+            })
+    }
+
+    /// Appends `page`.
+    pub fn push(&mut self, page: SharedPage<P>) {
+        self.set_offset(self.offset() + page.size());
+        self.pages.push(page);
+    }
+}
+
+impl<P: PointerKind> From<&str> for TextIn<P> {
+    fn from(str: &str) -> Self {
+        let mut builder = TextBuilderIn::default();
+        builder.push(str);
+        builder.done()
+    }
+}
+
+/// Returns `cluster` (at offset `i`) borrowed from `chunk` (at its offset), if
+/// it starts in it.
+fn borrow<'a>(
+    (offset, str): (usize, &'a str),
+    i: usize,
+    cluster: Cluster,
+) -> (usize, Cow<'a, str>) {
+    match i.checked_sub(offset) {
+        Some(start) => (i, Cow::Borrowed(&str[start..][..cluster.len()])),
+        None => (i, Cow::Owned(cluster.to_string())),
+    }
+}
+
+impl<P: PointerKind> Display for TextIn<P> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.pages
+            .iter()
+            .try_for_each(|(_, page)| page.chunks().try_for_each(|chunk| f.write_str(chunk)))
     }
 }
-*/
@@ -0,0 +1,372 @@
+use super::LineRef;
+use super::Lines;
+use super::TextIn;
+use crate::page::PointerKind;
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_segmentation::GraphemeCursor;
+use unicode_segmentation::GraphemeIncomplete;
+
+// Cursor motions, from and to byte offsets. Motions go through leading
+// spaces (or tabs) as through any content, and over eols as single clusters.
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the byte offset of the grapheme cluster following the one at
+    /// `byte` (or `byte` at the end), or `None` if out of bounds or not on a
+    /// char boundary.
+    ///
+    /// Eols are clusters too (`"\r\n"` as one).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::Text;
+    /// let text = Text::from("  fn main() {\r\n    e\u{301}\r\n}");
+    ///
+    /// assert!(text.next_grapheme(19) == Some(22)); // "e\u{301}"
+    /// assert!(text.prev_grapheme(15) == Some(13)); // "\r\n"
+    /// assert!(text.next_word(2) == Some(5)); // "main"
+    /// assert!(text.prev_word(15) == Some(12)); // "{"
+    /// assert!(text.line_end_of(3) == Some(13));
+    /// assert!(text.first_non_blank(15) == Some(19));
+    /// ```
+    pub fn next_grapheme(&self, byte: usize) -> Option<usize> {
+        let line = self.line_of(byte)?;
+        let at = byte - line.offset();
+        let chunks = LineChunks::new(&line);
+
+        if at == chunks.len {
+            Some(byte)
+        } else {
+            Some(line.offset() + chunks.next_boundary(at))
+        }
+    }
+
+    /// Returns the byte offset of the grapheme cluster preceding `byte` (or
+    /// `byte` at the start), or `None` if out of bounds or not on a char
+    /// boundary.
+    pub fn prev_grapheme(&self, byte: usize) -> Option<usize> {
+        let line = self.line_of(byte)?;
+        let at = byte - line.offset();
+
+        if at != 0 {
+            Some(line.offset() + LineChunks::new(&line).prev_boundary(at))
+        } else if let Some(line) = line.index().checked_sub(1).and_then(|i| self.line(i)) {
+            let chunks = LineChunks::new(&line);
+            Some(line.offset() + chunks.prev_boundary(chunks.len))
+        } else {
+            Some(byte)
+        }
+    }
+
+    /// Returns the byte offset of the start of the word following `byte` (or
+    /// the end of the text), or `None` if out of bounds or not on a char
+    /// boundary.
+    ///
+    /// Words are runs of alphanumeric (or `'_'`) clusters, or of other non
+    /// whitespace clusters (e.g. punctuation), and whitespace (eols included)
+    /// separates words.
+    pub fn next_word(&self, byte: usize) -> Option<usize> {
+        let mut graphemes = Graphemes::new(self, self.line_of(byte)?, byte);
+        let mut class = match graphemes.next() {
+            Some((_, char)) => Class::of(char),
+            None => return Some(byte),
+        };
+
+        for (start, char) in graphemes {
+            let next = Class::of(char);
+
+            if next != Class::Space && next != class {
+                return Some(start);
+            }
+
+            class = next;
+        }
+
+        Some(self.len)
+    }
+
+    /// Returns the byte offset of the start of the word preceding `byte` (or
+    /// containing it, if not at its start; or the start of the text), or
+    /// `None` if out of bounds or not on a char boundary.
+    ///
+    /// See [`Self::next_word`] for words.
+    pub fn prev_word(&self, byte: usize) -> Option<usize> {
+        let graphemes = RevGraphemes::new(self, self.line_of(byte)?, byte);
+        let mut class = Class::Space;
+        let mut start = byte;
+
+        for (prev, char) in graphemes {
+            let prev_class = Class::of(char);
+
+            if class != Class::Space && prev_class != class {
+                break;
+            }
+
+            class = prev_class;
+            start = prev;
+        }
+
+        Some(start)
+    }
+
+    /// Returns the byte offset of the start of the line of `byte`,
+    /// or `None` if out of bounds or not on a char boundary.
+    pub fn line_start_of(&self, byte: usize) -> Option<usize> {
+        self.line_of(byte).map(|line| line.offset())
+    }
+
+    /// Returns the byte offset of the end of the line of `byte` (before the
+    /// eol), or `None` if out of bounds or not on a char boundary.
+    pub fn line_end_of(&self, byte: usize) -> Option<usize> {
+        self.line_of(byte)
+            .map(|line| line.offset() + line.indent().len() + line.len())
+    }
+
+    /// Returns the byte offset of the first char of the line of `byte` which
+    /// is not a space nor a tab (or of the end of the line, if blank),
+    /// or `None` if out of bounds or not on a char boundary.
+    pub fn first_non_blank(&self, byte: usize) -> Option<usize> {
+        let line = self.line_of(byte)?;
+        let mut blanks = line.indent().len();
+
+        for str in line.content() {
+            let count = str.bytes().take_while(|&b| b == b' ' || b == b'\t').count();
+            blanks += count;
+
+            if count != str.len() {
+                break;
+            }
+        }
+
+        Some(line.offset() + blanks)
+    }
+
+    /// Returns the line of `byte`, or `None` if out of bounds or not on a char
+    /// boundary.
+    fn line_of(&self, byte: usize) -> Option<LineRef<'_, P>> {
+        let position = self.byte_to_position(byte)?;
+        self.line(position.line)
+    }
+}
+
+// =================
+
+/// Classes of grapheme clusters, for word motions.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Class {
+    Space,
+    Word,
+    Punctuation,
+}
+
+impl Class {
+    /// Returns the class of the cluster starting with `char`.
+    fn of(char: char) -> Self {
+        if char.is_whitespace() {
+            Self::Space
+        } else if char.is_alphanumeric() || char == '_' {
+            Self::Word
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
+/// The chunks of a line (leading spaces, content and eol), with their byte
+/// index in the line.
+pub(super) struct LineChunks<'a> {
+    chunks:         Vec<(usize, &'a str)>,
+    /// Len of the line, eol included.
+    pub(super) len: usize,
+}
+
+impl<'a> LineChunks<'a> {
+    pub(super) fn new<P: PointerKind>(line: &LineRef<'a, P>) -> Self {
+        let mut len = 0;
+        let chunks = line
+            .chunks()
+            .map(|str| {
+                len += str.len();
+                (len - str.len(), str)
+            })
+            .collect();
+
+        Self { chunks, len }
+    }
+
+    /// Returns the index of the chunk containing byte `at` (or ending at `at`,
+    /// if `end`).
+    fn chunk(&self, at: usize, end: bool) -> usize {
+        self.chunks.partition_point(|&(start, str)| {
+            let chunk_end = start + str.len();
+            chunk_end < at || (!end && chunk_end == at)
+        })
+    }
+
+    /// Returns the char at byte `at`.
+    fn char_at(&self, at: usize) -> Option<char> {
+        let (start, str) = self.chunks.get(self.chunk(at, false))?;
+        str[at - start..].chars().next()
+    }
+
+    /// Returns `true` if byte `at` is on a char boundary.
+    pub(super) fn is_char_boundary(&self, at: usize) -> bool {
+        self.chunks
+            .get(self.chunk(at, false))
+            .map_or(at == self.len, |&(start, str)| {
+                str.is_char_boundary(at - start)
+            })
+    }
+
+    /// Returns the `str` in `range` (on char boundaries), borrowed unless
+    /// over several chunks.
+    pub(super) fn str(&self, range: Range<usize>) -> Cow<'a, str> {
+        let mut strs = self.chunks[self.chunk(range.start, false)..]
+            .iter()
+            .take_while(|&&(start, _)| start < range.end)
+            .map(|&(start, str)| {
+                let from = range.start.saturating_sub(start);
+                let to = (range.end - start).min(str.len());
+                &str[from..to]
+            });
+        let first = strs.next().unwrap_or_default();
+
+        match strs.next() {
+            Some(second) => Cow::Owned([first, second].into_iter().chain(strs).collect()),
+            None => Cow::Borrowed(first),
+        }
+    }
+
+    /// Returns the cluster boundary following byte `at` (`< self.len`).
+    pub(super) fn next_boundary(&self, at: usize) -> usize {
+        let mut cursor = GraphemeCursor::new(at, self.len, true);
+        let mut i = self.chunk(at, false);
+
+        loop {
+            let (start, str) = self.chunks[i];
+
+            match cursor.next_boundary(str, start) {
+                Ok(boundary) => return boundary.unwrap_or(self.len),
+                Err(GraphemeIncomplete::NextChunk) => i += 1,
+                Err(GraphemeIncomplete::PreContext(end)) => self.provide_context(&mut cursor, end),
+                Err(err) => unreachable!("{:?}", err),
+            }
+        }
+    }
+
+    /// Returns the cluster boundary preceding byte `at` (`> 0`).
+    fn prev_boundary(&self, at: usize) -> usize {
+        let mut cursor = GraphemeCursor::new(at, self.len, true);
+        let mut i = self.chunk(at, true);
+
+        loop {
+            let (start, str) = self.chunks[i];
+
+            match cursor.prev_boundary(str, start) {
+                Ok(boundary) => return boundary.unwrap_or(0),
+                Err(GraphemeIncomplete::PrevChunk) => i -= 1,
+                Err(GraphemeIncomplete::PreContext(end)) => self.provide_context(&mut cursor, end),
+                Err(err) => unreachable!("{:?}", err),
+            }
+        }
+    }
+
+    /// Provides `cursor` with the context before byte `end`.
+    fn provide_context(&self, cursor: &mut GraphemeCursor, end: usize) {
+        let (start, str) = self.chunks[self.chunk(end, true)];
+        cursor.provide_context(&str[..end - start], start);
+    }
+}
+
+/// An iterator over the grapheme clusters from a byte offset, as their byte
+/// offset and first char.
+struct Graphemes<'a, P: PointerKind> {
+    lines:  Lines<'a, P>,
+    offset: usize,
+    chunks: LineChunks<'a>,
+    at:     usize,
+}
+
+impl<'a, P: PointerKind> Graphemes<'a, P> {
+    /// Starts at the cluster containing `byte`, in `line`.
+    fn new(text: &'a TextIn<P>, line: LineRef<'a, P>, byte: usize) -> Self {
+        let mut lines = text.lines_range(line.index()..).expect("valid line");
+        let chunks = LineChunks::new(&line);
+        let mut at = byte - line.offset();
+        lines.next();
+
+        if at != chunks.len {
+            at = chunks.prev_boundary(chunks.next_boundary(at));
+        }
+
+        Self {
+            lines,
+            offset: line.offset(),
+            chunks,
+            at,
+        }
+    }
+}
+
+impl<'a, P: PointerKind> Iterator for Graphemes<'a, P> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.at == self.chunks.len {
+            let line = self.lines.next()?;
+
+            self.offset = line.offset();
+            self.chunks = LineChunks::new(&line);
+            self.at = 0;
+        }
+
+        let start = self.at;
+        let char = self.chunks.char_at(start).expect("char at boundary");
+
+        self.at = self.chunks.next_boundary(start);
+        Some((self.offset + start, char))
+    }
+}
+
+/// An iterator over the grapheme clusters before a byte offset, backwards, as
+/// their byte offset and first char.
+struct RevGraphemes<'a, P: PointerKind> {
+    text:   &'a TextIn<P>,
+    line:   usize,
+    offset: usize,
+    chunks: LineChunks<'a>,
+    at:     usize,
+}
+
+impl<'a, P: PointerKind> RevGraphemes<'a, P> {
+    /// Starts at `byte`, in `line`.
+    fn new(text: &'a TextIn<P>, line: LineRef<'a, P>, byte: usize) -> Self {
+        Self {
+            text,
+            line: line.index(),
+            offset: line.offset(),
+            chunks: LineChunks::new(&line),
+            at: byte - line.offset(),
+        }
+    }
+}
+
+impl<'a, P: PointerKind> Iterator for RevGraphemes<'a, P> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.at == 0 {
+            self.line = self.line.checked_sub(1)?;
+            let line = self.text.line(self.line).expect("valid line");
+
+            self.offset = line.offset();
+            self.chunks = LineChunks::new(&line);
+            self.at = self.chunks.len;
+        }
+
+        self.at = self.chunks.prev_boundary(self.at);
+        let char = self.chunks.char_at(self.at).expect("char at boundary");
+
+        Some((self.offset + self.at, char))
+    }
+}
@@ -0,0 +1,360 @@
+use super::tree;
+use super::tree::Tree;
+use super::width::Widths;
+use super::WidthOptions;
+use crate::page::PointerKind;
+use crate::page::RcKind;
+use crate::page::SharedPage;
+use crate::Offset;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::iter::FromIterator;
+use std::ops::Range;
+use unicode_segmentation::GraphemeCursor;
+
+/// A balanced tree of [`SharedPage`]s.
+///
+/// Nodes aggregate the number, the [`Offset`] and the line widths (with the
+/// [`WidthOptions`] of the tree) of the pages under them, so that seeking (by
+/// any metric), splitting and splicing are `O(log n)`, and the widest line is
+/// known in `O(1)` once measured.
+///
+/// Pages are measured lazily: pushed and edited pages are only marked
+/// unmeasured, and measured when the widest line is asked for (see
+/// [`Pages::max_width`]), so that loading and editing never pay for widths.
+///
+/// Nodes are shared (behind `P` pointers, as are the bytes of the pages)
+/// between clones, and copied on write.
+#[derive(Clone, Default)]
+pub struct Pages<P: PointerKind = RcKind> {
+    tree:    Tree<Item<P>, P>,
+    options: WidthOptions,
+}
+
+/// A page, with its line widths (`None` if not measured yet).
+#[derive(Clone)]
+struct Item<P: PointerKind> {
+    page:   SharedPage<P>,
+    widths: Option<Widths>,
+}
+
+impl<P: PointerKind> Item<P> {
+    fn new(page: SharedPage<P>) -> Self {
+        Self { page, widths: None }
+    }
+}
+
+/// The size and line widths of pages (`None` if any is not measured yet).
+#[derive(Copy, Clone)]
+struct Summary {
+    size:   Offset,
+    widths: Option<Widths>,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self {
+            size:   Offset::default(),
+            widths: Some(Widths::default()),
+        }
+    }
+}
+
+impl<P: PointerKind> tree::Item for Item<P> {
+    type Summary = Summary;
+
+    fn summary(&self) -> Summary {
+        Summary {
+            size:   self.page.size(),
+            widths: self.widths,
+        }
+    }
+
+    fn combine(left: Summary, right: Summary) -> Summary {
+        Summary {
+            size:   left.size + right.size,
+            widths: left.widths.zip(right.widths).map(|(l, r)| l.then(r)),
+        }
+    }
+}
+
+impl<P: PointerKind> Pages<P> {
+    /// Returns the number of pages.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns `true` if there are no pages.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns the size of all pages, as an [`Offset`].
+    pub fn size(&self) -> Offset {
+        self.tree.summary().size
+    }
+
+    /// Returns the display width of the widest line.
+    ///
+    /// Measures the pages not measured yet (see [`Pages::measure`]), then
+    /// is `O(1)`.
+    pub fn max_width(&mut self) -> usize {
+        self.measure();
+        self.tree.summary().widths.expect("measured").max()
+    }
+
+    /// Measures the pages not measured yet, in `O(log n)` per page.
+    pub fn measure(&mut self) {
+        loop {
+            let index = self
+                .tree
+                .partition_point(|summary| summary.widths.is_some());
+
+            if index == self.len() {
+                break;
+            }
+            self.measure_page(index);
+        }
+    }
+
+    /// Returns the number of pages not measured yet.
+    pub fn unmeasured(&self) -> usize {
+        self.tree
+            .iter()
+            .filter(|(_, item)| item.widths.is_none())
+            .count()
+    }
+
+    /// Returns the display width options.
+    pub fn width_options(&self) -> WidthOptions {
+        self.options
+    }
+
+    /// Sets the display width options, to measure all pages again if they
+    /// changed.
+    pub fn set_width_options(&mut self, options: WidthOptions) {
+        if options != self.options {
+            self.options = options;
+            self.tree = self
+                .tree
+                .iter()
+                .map(|(_, item)| Item::new(item.page.clone()))
+                .collect();
+        }
+    }
+
+    /// Returns the offset and page at `index`, or `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<(Offset, &SharedPage<P>)> {
+        self.iter_from(index).next()
+    }
+
+    /// Returns an iterator over the pages, with their offsets.
+    pub fn iter(&self) -> Iter<'_, P> {
+        self.iter_from(0)
+    }
+
+    /// Returns an iterator over the pages from `index`, with their offsets.
+    pub fn iter_from(&self, index: usize) -> Iter<'_, P> {
+        Iter(self.tree.iter_from(index))
+    }
+
+    /// Returns the index of the first page whose end offset does not satisfy
+    /// `pred`, or `self.len()` if all do.
+    ///
+    /// As with [`slice::partition_point`], `pred` must be `true` then `false`
+    /// along the pages.
+    pub fn partition_point<F: FnMut(&Offset) -> bool>(&self, mut pred: F) -> usize {
+        self.tree.partition_point(|summary| pred(&summary.size))
+    }
+
+    /// Appends `page`.
+    pub fn push(&mut self, page: SharedPage<P>) {
+        let index = self.len();
+
+        self.tree.push(Item::new(page));
+
+        // The previous page may be continued in this one
+        if index != 0 && self.is_continued(index - 1) {
+            self.unmeasure(index - 1);
+        }
+    }
+
+    /// Updates the page at `index` with `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub(crate) fn update<F: FnOnce(&mut SharedPage<P>)>(&mut self, index: usize, f: F) {
+        self.tree.update(index, |item| {
+            f(&mut item.page);
+            item.widths = None;
+        });
+    }
+
+    /// Replaces the pages in `range` with `pages`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn splice<I: IntoIterator<Item = SharedPage<P>>>(&mut self, range: Range<usize>, pages: I) {
+        let len = self.len();
+
+        self.tree
+            .splice(range.clone(), pages.into_iter().map(Item::new));
+
+        // The pages around may be continued in the new ones
+        let end = range.start + self.len() + range.len() - len;
+        if range.start != 0 {
+            self.unmeasure(range.start - 1);
+        }
+        if end < self.len() {
+            self.unmeasure(end);
+        }
+    }
+
+    /// Returns these pages behind `Q` pointers, moving the bytes of the pages
+    /// not shared with other texts (see [`SharedPage::into_kind`]).
+    ///
+    /// Line widths are kept, not measured again.
+    pub fn into_kind<Q: PointerKind>(self) -> Pages<Q> {
+        let items = self
+            .tree
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect::<Vec<_>>();
+
+        // Drop the nodes (and their references to the pages) first
+        drop(self.tree);
+
+        Pages {
+            tree:    items
+                .into_iter()
+                .map(|item| Item {
+                    page:   item.page.into_kind(),
+                    widths: item.widths,
+                })
+                .collect(),
+            options: self.options,
+        }
+    }
+
+    /// Measures the page at `index` (see [`WidthOptions::page_widths`]).
+    fn measure_page(&mut self, index: usize) {
+        let (_, page) = self.get(index).expect("index in bounds");
+        let widths = self
+            .options
+            .page_widths(page, &self.before(index), &self.after(index));
+
+        self.tree.update(index, |item| item.widths = Some(widths));
+    }
+
+    /// Marks the page at `index` as not measured.
+    fn unmeasure(&mut self, index: usize) {
+        self.tree.update(index, |item| item.widths = None);
+    }
+
+    /// Returns the text of the line continued in the page at `index`, from
+    /// the start of the cluster continued in it (if any).
+    fn before(&self, index: usize) -> String {
+        let first = self.get(index).and_then(|(_, page)| page.records(0).next());
+        let first = first.map_or(String::new(), |record| {
+            format!("{}{}", record.meta.indent(), record.str)
+        });
+        let mut before = String::new();
+
+        for index in (0..index)
+            .rev()
+            .take_while(|&index| self.is_continued(index))
+        {
+            let (_, page) = self.get(index).expect("index in bounds");
+            let record = page.records(0).last().expect("continued record");
+
+            before.insert_str(0, record.str);
+            before.insert_str(0, record.meta.indent());
+
+            // Offset of `before` in the line, `1` if not known (yet)
+            let start = usize::from(index != 0 && self.is_continued(index - 1));
+            let chunk = format!("{before}{first}");
+            let mut cursor = GraphemeCursor::new(start + before.len(), start + chunk.len(), true);
+
+            match cursor.is_boundary(&chunk, start) {
+                Ok(true) => return String::new(),
+                Ok(false) => (),
+                Err(_) => continue,
+            }
+
+            match cursor.prev_boundary(&chunk, start) {
+                Ok(Some(boundary)) => return before.split_off(boundary - start),
+                Ok(None) => return before,
+                Err(_) => continue,
+            }
+        }
+
+        before
+    }
+
+    /// Returns the text of the line continued from the page at `index`, in
+    /// the next page.
+    fn after(&self, index: usize) -> String {
+        if !self.is_continued(index) {
+            return String::new();
+        }
+
+        self.get(index + 1)
+            .and_then(|(_, page)| page.records(0).next())
+            .map_or(String::new(), |record| {
+                format!("{}{}", record.meta.indent(), record.str)
+            })
+    }
+
+    /// Returns `true` if the last record of the page at `index` is continued.
+    fn is_continued(&self, index: usize) -> bool {
+        self.get(index).is_some_and(|(_, page)| page.is_continued())
+    }
+}
+
+impl<P: PointerKind> FromIterator<SharedPage<P>> for Pages<P> {
+    fn from_iter<I: IntoIterator<Item = SharedPage<P>>>(iter: I) -> Self {
+        let mut pages = Self::default();
+        iter.into_iter().for_each(|page| pages.push(page));
+        pages
+    }
+}
+
+impl<'a, P: PointerKind> IntoIterator for &'a Pages<P> {
+    type IntoIter = Iter<'a, P>;
+    type Item = (Offset, &'a SharedPage<P>);
+
+    fn into_iter(self) -> Iter<'a, P> {
+        self.iter()
+    }
+}
+
+impl<P: PointerKind> Debug for Pages<P> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|(_, page)| page))
+            .finish()
+    }
+}
+
+/// An iterator over [`Pages`], with their offsets.
+#[derive(Clone)]
+pub struct Iter<'a, P: PointerKind = RcKind>(tree::Iter<'a, Item<P>, P>);
+
+impl<'a, P: PointerKind> Iterator for Iter<'a, P> {
+    type Item = (Offset, &'a SharedPage<P>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .next()
+            .map(|(before, item)| (before.size, &item.page))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, P: PointerKind> ExactSizeIterator for Iter<'a, P> {}
@@ -0,0 +1,230 @@
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::page::Record;
+use crate::Eol;
+use crate::Offset;
+
+/// A position in a [`Text`]: a (0-based) line and column.
+///
+/// Columns are counted in bytes or in chars, depending on the conversion,
+/// leading spaces (or tabs) included.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default, Debug)]
+pub struct Position {
+    pub line:   usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Creates a new `Position`.
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the position (column in bytes) of byte `byte`,
+    /// or `None` if out of bounds or not on a char boundary.
+    ///
+    /// The byte between the `'\r'` and `'\n'` of a `"\r\n"` is a char
+    /// boundary, one column past the end of the line.
+    pub fn byte_to_position(&self, byte: usize) -> Option<Position> {
+        let offset = self.seek(byte, Metric::Len)?;
+        let (_, _, start) = self.line_start(offset.lines)?;
+
+        Some(Position::new(offset.lines, offset.len - start.len))
+    }
+
+    /// Returns the position (column in chars) of char `char`,
+    /// or `None` if out of bounds.
+    pub fn char_to_position(&self, char: usize) -> Option<Position> {
+        let offset = self.seek(char, Metric::Chars)?;
+        let (_, _, start) = self.line_start(offset.lines)?;
+
+        Some(Position::new(offset.lines, offset.chars - start.chars))
+    }
+
+    /// Returns the byte offset of `position` (column in bytes),
+    /// or `None` if out of bounds or not on a char boundary.
+    ///
+    /// The column must not be past the end of the line (eol excluded), but
+    /// for the middle of a `"\r\n"` (see [`Self::byte_to_position`]).
+    pub fn position_to_byte(&self, position: Position) -> Option<usize> {
+        let line = self.line(position.line)?;
+        let spaces = line.indent().len();
+
+        if position.column > spaces + line.len() + in_crlf(line.eol()) {
+            return None;
+        }
+
+        if let Some(mut column) = position.column.checked_sub(spaces) {
+            for str in line.content() {
+                if column <= str.len() {
+                    if !str.is_char_boundary(column) {
+                        return None;
+                    }
+
+                    break;
+                }
+
+                column -= str.len();
+            }
+        }
+
+        Some(line.offset() + position.column)
+    }
+
+    /// Returns the char offset of `position` (column in chars),
+    /// or `None` if out of bounds.
+    ///
+    /// The column must not be past the end of the line (eol excluded), but
+    /// for the middle of a `"\r\n"`.
+    pub fn position_to_char(&self, position: Position) -> Option<usize> {
+        let line = self.line(position.line)?;
+
+        if position.column > line.indent().len() + line.chars() + in_crlf(line.eol()) {
+            None
+        } else {
+            Some(line.char_offset() + position.column)
+        }
+    }
+
+    /// Returns the position (column in UTF-16 code units, as in LSP) of byte
+    /// `byte`, or `None` if out of bounds or not on a char boundary.
+    pub fn byte_to_utf16_position(&self, byte: usize) -> Option<Position> {
+        let offset = self.seek(byte, Metric::Len)?;
+        let (_, _, start) = self.line_start(offset.lines)?;
+
+        Some(Position::new(offset.lines, offset.utf16 - start.utf16))
+    }
+
+    /// Returns the byte offset of `position` (column in UTF-16 code units, as
+    /// in LSP), or `None` if out of bounds or in a surrogate pair.
+    ///
+    /// The column must not be past the end of the line (eol excluded), but
+    /// for the middle of a `"\r\n"`.
+    pub fn utf16_position_to_byte(&self, position: Position) -> Option<usize> {
+        let line = self.line(position.line)?;
+
+        if position.column > line.indent().len() + line.utf16() + in_crlf(line.eol()) {
+            return None;
+        }
+
+        let offset = self.seek(line.utf16_offset() + position.column, Metric::Utf16)?;
+        Some(offset.len)
+    }
+
+    /// Returns the offset at `target` (in `metric`),
+    /// or `None` if out of bounds or not on a char boundary.
+    pub(crate) fn seek(&self, target: usize, metric: Metric) -> Option<Offset> {
+        let end = self.offset();
+
+        if target >= metric.offset(&end) {
+            return (target == metric.offset(&end)).then_some(end);
+        }
+
+        let page = self
+            .pages
+            .partition_point(|end| metric.offset(end) <= target);
+        let (mut offset, page) = self.pages.get(page).expect("target in pages");
+
+        for record in page.records(0) {
+            let at = target - metric.offset(&offset);
+
+            if at < metric.record(&record) {
+                offset += metric.split(&record, at)?;
+                return Some(offset);
+            }
+
+            offset += record.offset();
+        }
+
+        unreachable!("target is in page");
+    }
+}
+
+/// Returns the number of columns inside `eol`: `1` for `"\r\n"`, else `0`.
+fn in_crlf(eol: Option<Eol>) -> usize {
+    (eol == Some(Eol::CRLF)) as usize
+}
+
+/// A metric of [`Offset`]s.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Metric {
+    /// Bytes.
+    Len,
+    /// Chars.
+    Chars,
+    /// UTF-16 code units.
+    Utf16,
+}
+
+impl Metric {
+    fn offset(self, offset: &Offset) -> usize {
+        match self {
+            Self::Len => offset.len,
+            Self::Chars => offset.chars,
+            Self::Utf16 => offset.utf16,
+        }
+    }
+
+    fn record(self, record: &Record) -> usize {
+        match self {
+            Self::Len => record.len(),
+            Self::Chars => record.chars(),
+            Self::Utf16 => record.utf16(),
+        }
+    }
+
+    /// Returns the offset of `at` (in `self`) in `record` (without eols),
+    /// or `None` if not on a char boundary.
+    fn split(self, record: &Record, at: usize) -> Option<Offset> {
+        let spaces = record.meta.spaces as usize;
+        let str = record.str;
+
+        if at <= spaces {
+            return Some(Offset {
+                len:   at,
+                chars: at,
+                utf16: at,
+                lines: 0,
+            });
+        }
+
+        let at = at - spaces;
+        let mut offset = Offset::default();
+
+        for char in str.chars() {
+            let current = self.offset(&offset);
+
+            if current == at {
+                break;
+            } else if current > at {
+                return None;
+            }
+
+            offset.len += char.len_utf8();
+            offset.chars += 1;
+            offset.utf16 += char.len_utf16();
+        }
+
+        let current = self.offset(&offset);
+        if current < at {
+            // In the eol: only the middle of `"\r\n"` is a char boundary
+            if current + 1 == at && record.meta.eol == Some(Eol::CRLF) {
+                offset.len += 1;
+                offset.chars += 1;
+                offset.utf16 += 1;
+            } else {
+                return None;
+            }
+        } else if current > at {
+            return None;
+        }
+
+        offset.len += spaces;
+        offset.chars += spaces;
+        offset.utf16 += spaces;
+        Some(offset)
+    }
+}
@@ -1,3 +1,7 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
 use std::io;
 use std::io::Read;
@@ -6,24 +10,55 @@ use std::str::from_utf8;
 
 const CAPACITY: usize = 8 * 1024;
 
+/// An error raised when reading a [`Text`](super::Text).
+#[derive(Debug)]
+pub enum ReadError {
+    /// An I/O error.
+    Io(io::Error),
+    /// Invalid UTF-8, starting at byte `offset`.
+    Utf8 { offset: usize },
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Utf8 { offset } => write!(f, "invalid UTF-8 at byte {}", offset),
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 /// Reads and validates (UTF-8) bytes.
+///
+/// Never splits `"\r\n"`s: a trailing `'\r'` is kept for the next read.
 #[derive(Copy, Clone, Debug)]
 pub struct Reader<T: Read> {
     reader: T,
     buffer: [u8; CAPACITY],
     len:    usize,
     valid:  usize,
+    offset: usize,
 }
 
 impl Reader<File> {
     /// Creates a new `Reader` from a file path.
     pub fn open<P: AsRef<Path>>(file: P) -> io::Result<Self> {
-        Ok(Self {
-            reader: File::open(file)?,
-            buffer: [0; CAPACITY],
-            len:    0,
-            valid:  CAPACITY,
-        })
+        Ok(Self::new(File::open(file)?))
     }
 }
 
@@ -34,47 +69,75 @@ impl<T: Read> Reader<T> {
             reader,
             buffer: [0; CAPACITY],
             len: 0,
-            valid: CAPACITY,
+            valid: 0,
+            offset: 0,
         }
     }
 
     /// Reads from the reader, returning:
-    /// - `Err`: io error
+    /// - `Err`: io or UTF-8 validation error
     /// - `Ok`:
     ///   - `None`: end of stream
-    ///   - `Some`:
-    ///     - `None`: UTF-8 validation error
-    ///     - `Some`: `&str`
-    pub fn read(&mut self) -> io::Result<Option<Option<&str>>> {
-        // TODO: do not split `\r\n`s!
-
-        let invalid = self.prepare_buffer();
-        let len = self.reader.read(&mut self.buffer[invalid..])?;
-        let bytes = &self.buffer[..len];
-
-        Ok(if !bytes.is_empty() {
-            Some(match from_utf8(bytes) {
-                Ok(str) => Some(str),
+    ///   - `Some`: `&str`
+    pub fn read(&mut self) -> Result<Option<&str>, ReadError> {
+        self.prepare_buffer();
+
+        loop {
+            let len = match self.reader.read(&mut self.buffer[self.len..]) {
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            if len == 0 {
+                return self.done();
+            }
+
+            self.len += len;
+            self.valid = match from_utf8(&self.buffer[..self.len]) {
+                Ok(str) => str.len(),
                 Err(err) => match err.error_len() {
-                    Some(_) => None,
-                    None => {
-                        self.valid = err.valid_up_to();
-                        Some(unsafe { utf8!(&bytes[..self.valid]) })
-                    }
+                    Some(_) => return Err(self.error(err.valid_up_to())),
+                    None => err.valid_up_to(),
                 },
-            })
-        } else {
-            None
-        })
+            };
+
+            if self.buffer[..self.valid].last() == Some(&b'\r') {
+                self.valid -= 1;
+            }
+
+            if self.valid != 0 {
+                return Ok(Some(unsafe { utf8!(&self.buffer[..self.valid]) }));
+            }
+        }
     }
 
-    /// Copies invalid bytes at the end of the buffer to the start,
-    /// returning the amout.
-    fn prepare_buffer(&mut self) -> usize {
-        // NOTE: this works only if CAPACITY >= 8
-        let (left, right) = self.buffer.split_at_mut(self.valid);
-        left[..right.len()].copy_from_slice(right);
+    /// Returns the remaining (carried) bytes at the end of the stream.
+    fn done(&mut self) -> Result<Option<&str>, ReadError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
 
-        right.len()
+        match from_utf8(&self.buffer[..self.len]) {
+            Ok(str) => {
+                self.valid = self.len;
+                Ok(Some(str))
+            }
+            Err(err) => Err(self.error(err.valid_up_to())),
+        }
+    }
+
+    /// Copies bytes after the valid ones to the start of the buffer.
+    fn prepare_buffer(&mut self) {
+        self.buffer.copy_within(self.valid..self.len, 0);
+        self.offset += self.valid;
+        self.len -= self.valid;
+        self.valid = 0;
+    }
+
+    fn error(&self, valid: usize) -> ReadError {
+        ReadError::Utf8 {
+            offset: self.offset + valid,
+        }
     }
 }
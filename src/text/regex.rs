@@ -0,0 +1,300 @@
+use super::Edit;
+use super::Match;
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::utils;
+use regex_automata::hybrid::dfa::Cache;
+use regex_automata::hybrid::dfa::DFA;
+use regex_automata::hybrid::BuildError;
+use regex_automata::meta;
+use regex_automata::nfa::thompson;
+use regex_automata::util::start;
+use regex_automata::Anchored;
+use regex_automata::Input;
+use regex_automata::MatchKind;
+use std::ops::Range;
+
+/// A regular expression, to search a [`Text`] (see [`Text::find_regex_iter`]).
+///
+/// Searches stream the chunks of the text through lazy DFAs: a forward one
+/// finds the end of the leftmost match, then a reverse one its start.
+///
+/// The syntax is the one of the [`regex`](https://docs.rs/regex) crate.
+/// `^` and `$` match at the start and end of the text, or of lines with the
+/// `m` flag (only after and before `'\n'`).
+/// Unicode word boundaries are not supported: use `(?-u:\b)` instead.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    forward:  DFA,
+    reverse:  DFA,
+    /// For capture groups, on matches only.
+    captures: meta::Regex,
+}
+
+impl Regex {
+    /// Compiles `pattern`.
+    pub fn new(pattern: &str) -> Result<Self, Box<BuildError>> {
+        let forward = DFA::new(pattern)?;
+        let reverse = DFA::builder()
+            .configure(DFA::config().match_kind(MatchKind::All))
+            .thompson(thompson::Config::new().reverse(true))
+            .build(pattern)?;
+        let captures = meta::Regex::new(pattern).expect("supported by lazy DFAs");
+
+        Ok(Self {
+            forward,
+            reverse,
+            captures,
+        })
+    }
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the first match of `regex`, or `None` if not found.
+    ///
+    /// See [`Self::find_regex_iter`].
+    pub fn find_regex(&self, regex: &Regex) -> Option<Match> {
+        self.find_regex_iter(regex).next()
+    }
+
+    /// Returns an iterator over the (non-overlapping) matches of `regex`.
+    ///
+    /// As with [`Self::find_iter`], the text is searched chunk by chunk.
+    /// Empty matches are not reported right after a match, nor inside chars.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::text::Position;
+    /// # use grappe::text::Regex;
+    /// # use grappe::Text;
+    /// let text = Text::from("fn main() {\n    let a = 1;\n    let b = 22;\n}");
+    /// let regex = Regex::new(r"(?m)let \w+ = \d+;$").unwrap();
+    /// let matches = text.find_regex_iter(&regex).collect::<Vec<_>>();
+    ///
+    /// assert!(matches.len() == 2);
+    /// assert!(matches[1].range == (31..42));
+    /// assert!(matches[1].start == Position::new(2, 4));
+    /// ```
+    pub fn find_regex_iter<'a>(&'a self, regex: &'a Regex) -> impl 'a + Iterator<Item = Match> {
+        let mut searcher = Searcher {
+            regex,
+            forward: regex.forward.create_cache(),
+            reverse: regex.reverse.create_cache(),
+        };
+        let mut from = Some(0);
+        let mut last = None;
+
+        std::iter::from_fn(move || loop {
+            let range = match searcher.find(self, from?) {
+                Some(range) => range,
+                None => {
+                    from = None;
+                    return None;
+                }
+            };
+
+            if !range.is_empty() {
+                from = Some(range.end);
+            } else {
+                from = self.next_char_boundary(range.end);
+
+                if last == Some(range.end) || !self.is_char_boundary(range.end) {
+                    continue;
+                }
+            }
+
+            last = Some(range.end);
+            return Some(self.to_match(range));
+        })
+    }
+
+    /// Returns an iterator over the edits replacing the (non-overlapping)
+    /// matches of `regex` by `replacement`.
+    ///
+    /// `replacement` can refer to capture groups by index or name, as `$1`
+    /// or `${name}` (`$$` for a `$`).
+    ///
+    /// See [`Self::find_regex_iter`].
+    pub fn replace_regex_iter<'a>(
+        &'a self,
+        regex: &'a Regex,
+        replacement: &'a str,
+    ) -> impl 'a + Iterator<Item = Edit> {
+        let mut captures = regex.captures.create_captures();
+        let mut haystack = Vec::new();
+
+        self.find_regex_iter(regex).map(move |found| {
+            let range = found.range;
+
+            // The match, with the bytes around for look-arounds
+            let start = range.start.saturating_sub(1);
+            let end = (range.end + 1).min(self.len);
+            haystack.clear();
+            for (offset, bytes) in self.chunks_from(start) {
+                if offset >= end {
+                    break;
+                }
+                haystack.extend_from_slice(&bytes[..bytes.len().min(end - offset)]);
+            }
+
+            let input = Input::new(&haystack)
+                .range(range.start - start..range.end - start)
+                .anchored(Anchored::Yes);
+            regex.captures.search_captures(&input, &mut captures);
+
+            let mut str = Vec::new();
+            captures.interpolate_bytes_into(&haystack, replacement.as_bytes(), &mut str);
+
+            Edit {
+                range,
+                str: String::from_utf8(str).expect("groups on char boundaries"),
+            }
+        })
+    }
+
+    /// Returns a snapshot of this text with the matches of `regex` replaced
+    /// by `replacement`, and the edits (ranges in this text, sorted).
+    ///
+    /// As with [`Self::replace_all`], only the pages with matches are
+    /// rebuilt. See [`Self::replace_regex_iter`] for `replacement`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::text::Regex;
+    /// # use grappe::Text;
+    /// let text = Text::from("let a = 1;\nlet bc = 22;\n");
+    /// let regex = Regex::new(r"let (?<name>\w+) = (\d+)").unwrap();
+    /// let (replaced, edits) = text.replace_regex_all(&regex, "const ${name}: u8 = $2");
+    ///
+    /// assert!(replaced.to_string() == "const a: u8 = 1;\nconst bc: u8 = 22;\n");
+    /// assert!(edits.len() == 2);
+    /// assert!(edits[1].range == (11..22));
+    /// assert!(edits[1].str == "const bc: u8 = 22");
+    /// ```
+    pub fn replace_regex_all(&self, regex: &Regex, replacement: &str) -> (Self, Vec<Edit>) {
+        self.replace_with(self.replace_regex_iter(regex, replacement))
+    }
+
+    /// Returns the char boundary following byte `byte`, or `None` at the end.
+    fn next_char_boundary(&self, byte: usize) -> Option<usize> {
+        if byte == self.len {
+            return None;
+        }
+
+        let boundary = self
+            .chunks_from(byte + 1)
+            .flat_map(|(offset, bytes)| (offset..).zip(bytes))
+            .find(|&(_, &byte)| !utils::is_continuation(byte))
+            .map_or(self.len, |(offset, _)| offset);
+
+        Some(boundary)
+    }
+
+    /// Returns `true` if byte `byte` is on a char boundary.
+    fn is_char_boundary(&self, byte: usize) -> bool {
+        self.chunks_from(byte)
+            .next()
+            .is_none_or(|(_, bytes)| !utils::is_continuation(bytes[0]))
+    }
+}
+
+/// A [`Regex`] with its caches.
+struct Searcher<'a> {
+    regex:   &'a Regex,
+    forward: Cache,
+    reverse: Cache,
+}
+
+impl<'a> Searcher<'a> {
+    /// Returns the range of the leftmost match in `text` from byte `from`,
+    /// or `None` if not found.
+    fn find<P: PointerKind>(&mut self, text: &TextIn<P>, from: usize) -> Option<Range<usize>> {
+        let end = self.find_end(text, from)?;
+        let start = self.find_start(text, from, end)?;
+
+        Some(start..end)
+    }
+
+    /// Returns the end of the leftmost match from `from`.
+    fn find_end<P: PointerKind>(&mut self, text: &TextIn<P>, from: usize) -> Option<usize> {
+        let dfa = &self.regex.forward;
+        let cache = &mut self.forward;
+        let look_behind = text
+            .rev_chunks(from)
+            .next()
+            .map(|(_, bytes)| bytes[bytes.len() - 1]);
+        let config = start::Config::new()
+            .anchored(Anchored::No)
+            .look_behind(look_behind);
+        let mut state = dfa.start_state(cache, &config).expect(NO_QUIT);
+        let mut end = None;
+
+        // Matches are delayed by a byte
+        for (offset, bytes) in text.chunks_from(from) {
+            for (i, &byte) in bytes.iter().enumerate() {
+                state = dfa.next_state(cache, state, byte).expect(NO_GIVE_UP);
+
+                if state.is_match() {
+                    end = Some(offset + i);
+                } else if state.is_dead() {
+                    return end;
+                }
+            }
+        }
+
+        state = dfa.next_eoi_state(cache, state).expect(NO_GIVE_UP);
+        if state.is_match() {
+            end = Some(text.len);
+        }
+
+        end
+    }
+
+    /// Returns the start of the match (from `from`) ending at `end`.
+    fn find_start<P: PointerKind>(
+        &mut self,
+        text: &TextIn<P>,
+        from: usize,
+        end: usize,
+    ) -> Option<usize> {
+        let dfa = &self.regex.reverse;
+        let cache = &mut self.reverse;
+        let look_ahead = text.chunks_from(end).next().map(|(_, bytes)| bytes[0]);
+        let config = start::Config::new()
+            .anchored(Anchored::Yes)
+            .look_behind(look_ahead);
+        let mut state = dfa.start_state(cache, &config).expect(NO_QUIT);
+        let mut start = None;
+
+        // Matches are delayed by a byte (the one before `from` included)
+        for (offset, bytes) in text.rev_chunks(end) {
+            for (i, &byte) in bytes.iter().enumerate().rev() {
+                state = dfa.next_state(cache, state, byte).expect(NO_GIVE_UP);
+
+                if state.is_match() {
+                    start = Some(offset + i + 1);
+                }
+                if state.is_dead() || offset + i < from {
+                    return start;
+                }
+            }
+        }
+
+        state = dfa.next_eoi_state(cache, state).expect(NO_GIVE_UP);
+        if state.is_match() {
+            start = Some(0);
+        }
+
+        start
+    }
+}
+
+/// Lazy DFAs only quit on non-ASCII bytes with Unicode word boundaries,
+/// rejected when building.
+const NO_QUIT: &str = "no quit bytes";
+
+/// Lazy DFAs only give up when configured to.
+const NO_GIVE_UP: &str = "lazy DFA does not give up";
@@ -0,0 +1,162 @@
+use super::Text;
+use super::TextIn;
+use super::WriteOptions;
+use crate::page::PointerKind;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Options for saving a [`Text`] (see [`Text::save`]).
+///
+/// Texts are always saved as UTF-8 (a BOM, if any, being part of the text).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SaveOptions {
+    /// Write options.
+    pub write:        WriteOptions,
+    /// Whether to replace all eols with the eol of the file (see
+    /// [`TextIn::file_eol`]), or the dominant eol of the text if not read
+    /// from a file, when [`WriteOptions::eol`] is `None`.
+    ///
+    /// `true` by default, so that lines inserted with `'\n'`s in a `"\r\n"`
+    /// file are saved with `"\r\n"`s. `false` writes eols as is.
+    pub dominant_eol: bool,
+    /// Whether to keep the previous file as a backup, suffixed with `~`.
+    pub backup:       bool,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            write:        WriteOptions::default(),
+            dominant_eol: true,
+            backup:       false,
+        }
+    }
+}
+
+/// Counter of temporary files, for concurrent saves.
+static TEMPORARIES: AtomicUsize = AtomicUsize::new(0);
+
+impl<P: PointerKind> TextIn<P> {
+    /// Saves this text to `path`, atomically.
+    ///
+    /// The text is written to a temporary file next to `path`, synced, then
+    /// renamed over `path`: `path` is never left half-written, even on
+    /// crashes. The permissions of the previous file are kept, and symlinks
+    /// are followed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use grappe::text::FinalEol;
+    /// # use grappe::text::SaveOptions;
+    /// # use grappe::Text;
+    /// let mut text = Text::open("notes.txt")?;
+    /// text.insert(0, "TODO\n");
+    ///
+    /// let mut options = SaveOptions::default();
+    /// options.write.final_eol = FinalEol::Ensure;
+    /// options.backup = true;
+    ///
+    /// text.save("notes.txt", options)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn save<T: AsRef<Path>>(&self, path: T, options: SaveOptions) -> io::Result<()> {
+        let path = match fs::canonicalize(path.as_ref()) {
+            Ok(path) => path,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => path.as_ref().to_owned(),
+            Err(err) => return Err(err),
+        };
+        let previous = match fs::metadata(&path) {
+            Ok(metadata) => Some(metadata),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+
+        let mut write = options.write;
+        if options.dominant_eol && write.eol.is_none() {
+            write.eol = self.file_eol.or_else(|| self.dominant_eol());
+        }
+
+        let temporary = sibling(&path, |name| {
+            let count = TEMPORARIES.fetch_add(1, Ordering::Relaxed);
+            format!(".{}.{}.{}.tmp", name, std::process::id(), count)
+        })?;
+        let result = (|| {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temporary)?;
+
+            if let Some(previous) = &previous {
+                file.set_permissions(previous.permissions())?;
+            }
+
+            self.write_to_with(&mut file, write)?;
+            file.sync_all()?;
+
+            if options.backup && previous.is_some() {
+                backup(&path)?;
+            }
+
+            fs::rename(&temporary, &path)
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+            return result;
+        }
+
+        sync_parent(&path)
+    }
+}
+
+/// Returns the path of a file named by `name` (from the file name of `path`)
+/// in the directory of `path`.
+fn sibling<F: FnOnce(&str) -> String>(path: &Path, name: F) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+
+    Ok(path.with_file_name(name(&file_name.to_string_lossy())))
+}
+
+/// Keeps a copy of the file at `path`, suffixed with `~`.
+///
+/// Hard links when possible, copies otherwise.
+fn backup(path: &Path) -> io::Result<()> {
+    let mut name = OsString::from(path.as_os_str());
+    name.push("~");
+    let backup = PathBuf::from(name);
+
+    match fs::remove_file(&backup) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+
+    if fs::hard_link(path, &backup).is_err() {
+        fs::copy(path, &backup)?;
+    }
+
+    Ok(())
+}
+
+/// Syncs the directory of `path`, for renames to be durable.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    if cfg!(unix) {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            File::open(parent)?.sync_all()?;
+        }
+    }
+
+    Ok(())
+}
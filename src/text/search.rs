@@ -0,0 +1,260 @@
+use super::Edit;
+use super::Position;
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// A match of a search in a [`Text`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Match {
+    /// Byte range of the match.
+    pub range: Range<usize>,
+    /// Position (column in bytes) of the start of the match.
+    pub start: Position,
+    /// Position (column in bytes) of the end of the match.
+    pub end:   Position,
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the first match of `pattern`, or `None` if not found.
+    ///
+    /// See [`Self::find_iter`].
+    pub fn find(&self, pattern: &str) -> Option<Match> {
+        self.find_iter(pattern).next()
+    }
+
+    /// Returns an iterator over the (non-overlapping) matches of `pattern`.
+    ///
+    /// The text is searched chunk by chunk, leading spaces and eols included,
+    /// without materializing a `String`: matches can span pages.
+    /// An empty pattern matches nowhere.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::text::Position;
+    /// # use grappe::Text;
+    /// let text = Text::from("fn main() {\n    let a = 1;\n    let b = a;\n}");
+    /// let matches = text.find_iter("a").collect::<Vec<_>>();
+    ///
+    /// assert!(matches.len() == 3);
+    /// assert!(matches[1].range == (20..21));
+    /// assert!(matches[1].start == Position::new(1, 8));
+    ///
+    /// let found = text.find("\n    let").unwrap();
+    /// assert!(found.range == (11..19));
+    /// assert!(found.end == Position::new(1, 7));
+    /// ```
+    pub fn find_iter<'a>(&'a self, pattern: &'a str) -> impl 'a + Iterator<Item = Match> {
+        let mut chunks = self.chunks_from(0);
+        let mut finder = Finder::new(pattern.as_bytes());
+        let mut matches = VecDeque::new();
+
+        std::iter::from_fn(move || loop {
+            if let Some(range) = matches.pop_front() {
+                return Some(self.to_match(range));
+            }
+
+            let (offset, chunk) = chunks.next()?;
+            finder.find(offset, chunk, &mut matches);
+        })
+    }
+
+    /// Returns an iterator over the edits replacing the (non-overlapping)
+    /// matches of `pattern` by `replacement`.
+    ///
+    /// See [`Self::find_iter`].
+    pub fn replace_iter<'a>(
+        &'a self,
+        pattern: &'a str,
+        replacement: &'a str,
+    ) -> impl 'a + Iterator<Item = Edit> {
+        self.find_iter(pattern).map(move |found| Edit {
+            range: found.range,
+            str:   replacement.to_owned(),
+        })
+    }
+
+    /// Returns a snapshot of this text with the matches of `pattern` replaced
+    /// by `replacement`, and the edits (ranges in this text, sorted).
+    ///
+    /// Only the pages with matches are rebuilt, other pages are shared with
+    /// this text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::Text;
+    /// let text = Text::from("let a = 1;\nlet b = a;\n");
+    /// let (replaced, edits) = text.replace_all("let", "const");
+    ///
+    /// assert!(replaced.to_string() == "const a = 1;\nconst b = a;\n");
+    /// assert!(edits.len() == 2);
+    /// assert!(edits[1].range == (11..14));
+    /// ```
+    pub fn replace_all(&self, pattern: &str, replacement: &str) -> (Self, Vec<Edit>) {
+        self.replace_with(self.replace_iter(pattern, replacement))
+    }
+
+    /// Returns a snapshot of this text with `edits` (sorted, not overlapping)
+    /// applied, and the edits.
+    pub(super) fn replace_with<I: IntoIterator<Item = Edit>>(&self, edits: I) -> (Self, Vec<Edit>) {
+        let edits = edits.into_iter().collect::<Vec<_>>();
+        let mut text = self.snapshot();
+        text.replace_ranges(&edits);
+
+        (text, edits)
+    }
+
+    /// Returns the match of byte range `range`.
+    pub(super) fn to_match(&self, range: Range<usize>) -> Match {
+        Match {
+            start: self.byte_to_position(range.start).expect("match in bounds"),
+            end: self.byte_to_position(range.end).expect("match in bounds"),
+            range,
+        }
+    }
+
+    /// Returns an iterator over the bytes of the chunks from byte `from`,
+    /// with their offset.
+    pub(super) fn chunks_from(&self, from: usize) -> impl '_ + Iterator<Item = (usize, &[u8])> {
+        let page = self.pages.partition_point(|end| end.len <= from);
+
+        self.pages
+            .iter_from(page)
+            .flat_map(|(offset, page)| offsets(offset.len, page.chunks()))
+            .filter_map(move |(offset, bytes)| match from.checked_sub(offset) {
+                Some(start) => bytes
+                    .get(start..)
+                    .filter(|bytes| !bytes.is_empty())
+                    .map(|bytes| (from, bytes)),
+                None => Some((offset, bytes)),
+            })
+    }
+
+    /// Returns an iterator over the bytes of the chunks before byte `end`,
+    /// backwards, with their offset.
+    pub(super) fn rev_chunks(&self, end: usize) -> impl '_ + Iterator<Item = (usize, &[u8])> {
+        let page = self.pages.partition_point(|page_end| page_end.len < end);
+
+        (0..=page)
+            .rev()
+            .filter_map(move |page| self.pages.get(page))
+            .flat_map(|(offset, page)| {
+                offsets(offset.len, page.chunks())
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .rev()
+            })
+            .filter_map(move |(offset, bytes)| {
+                let len = end.checked_sub(offset)?.min(bytes.len());
+                (len != 0).then(|| (offset, &bytes[..len]))
+            })
+    }
+}
+
+/// Returns `chunks` as bytes, with their offset (from `offset`).
+fn offsets<'a, I: Iterator<Item = &'a str>>(
+    mut offset: usize,
+    chunks: I,
+) -> impl Iterator<Item = (usize, &'a [u8])> {
+    chunks.map(move |str| {
+        offset += str.len();
+        (offset - str.len(), str.as_bytes())
+    })
+}
+
+/// Finds a literal in a stream of chunks, matches possibly spanning chunks.
+struct Finder<'a> {
+    needle: &'a [u8],
+    /// The last bytes of the stream (less than `needle`), and their offset.
+    carry:  (usize, Vec<u8>),
+    /// Offset from which matches can start (after the previous match).
+    min:    usize,
+    /// Buffer of the carry and the start of the next chunk.
+    window: Vec<u8>,
+}
+
+impl<'a> Finder<'a> {
+    fn new(needle: &'a [u8]) -> Self {
+        Self {
+            needle,
+            carry: (0, Vec::new()),
+            min: 0,
+            window: Vec::new(),
+        }
+    }
+
+    /// Pushes the ranges of the matches ending in `chunk` (at `offset`) to
+    /// `matches`.
+    fn find(&mut self, offset: usize, chunk: &[u8], matches: &mut VecDeque<Range<usize>>) {
+        let len = self.needle.len();
+        if len == 0 {
+            return;
+        }
+
+        // A match spanning the previous chunks and this one
+        let (carry_start, carry) = &self.carry;
+        if !carry.is_empty() {
+            let from = self.min.saturating_sub(*carry_start);
+
+            self.window.clear();
+            self.window.extend_from_slice(carry);
+            self.window
+                .extend_from_slice(&chunk[..(len - 1).min(chunk.len())]);
+
+            if let Some(i) = self
+                .window
+                .get(from..)
+                .and_then(|window| position(window, self.needle))
+            {
+                if from + i < carry.len() {
+                    let start = carry_start + from + i;
+                    matches.push_back(start..start + len);
+                    self.min = start + len;
+                }
+            }
+        }
+
+        // Matches in this chunk
+        let mut at = self.min.saturating_sub(offset);
+        while let Some(i) = chunk
+            .get(at..)
+            .and_then(|chunk| position(chunk, self.needle))
+        {
+            let start = offset + at + i;
+            matches.push_back(start..start + len);
+            self.min = start + len;
+            at += i + len;
+        }
+
+        // Keep the last bytes, for the next chunk
+        let (carry_start, carry) = &mut self.carry;
+        let keep = (len - 1).min(carry.len() + chunk.len());
+        let from_carry = keep.saturating_sub(chunk.len());
+        carry.drain(..carry.len() - from_carry);
+        carry.extend_from_slice(&chunk[chunk.len() - (keep - from_carry)..]);
+        *carry_start = offset + chunk.len() - carry.len();
+    }
+}
+
+/// Returns the index of the first occurrence of `needle` (not empty) in
+/// `haystack`.
+fn position(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let (&first, rest) = needle.split_first()?;
+    let mut at = 0;
+
+    while let Some(i) = haystack.get(at..)?.iter().position(|&byte| byte == first) {
+        let start = at + i;
+
+        if haystack[start + 1..].starts_with(rest) {
+            return Some(start);
+        }
+
+        at = start + 1;
+    }
+
+    None
+}
@@ -0,0 +1,74 @@
+use super::Text;
+use super::TextIn;
+use crate::page::ArcKind;
+use crate::page::PointerKind;
+
+/// A [`TextIn`] stored in [`Arc`](std::sync::Arc)s, which can be sent and
+/// shared across threads.
+///
+/// It has the same API as a [`Text`]: use it to load a text on a background
+/// thread, or to hand a text to workers. Converting a text from and to a
+/// [`Text`] moves the pages it does not share with other texts (e.g.
+/// snapshots), and copies the others.
+///
+/// # Usage
+///
+/// ```
+/// # use grappe::text::SyncText;
+/// # use grappe::Text;
+/// let text = Text::from("Hello, world!");
+/// let mut sync = SyncText::from(text);
+///
+/// let text = std::thread::spawn(move || {
+///     sync.insert(13, "\n");
+///     sync
+/// })
+/// .join()
+/// .unwrap();
+///
+/// let text = Text::from(text);
+/// assert!(text.to_string() == "Hello, world!\n");
+/// ```
+pub type SyncText = TextIn<ArcKind>;
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns this text with its pages behind `Q` pointers (see
+    /// [`Pages::into_kind`](super::Pages::into_kind)).
+    fn into_kind<Q: PointerKind>(self) -> TextIn<Q> {
+        TextIn {
+            len:      self.len,
+            chars:    self.chars,
+            utf16:    self.utf16,
+            lines:    self.lines,
+            pages:    self.pages.into_kind(),
+            eols:     self.eols,
+            file_eol: self.file_eol,
+        }
+    }
+}
+
+impl From<Text> for SyncText {
+    fn from(text: Text) -> Self {
+        text.into_kind()
+    }
+}
+
+impl From<SyncText> for Text {
+    fn from(text: SyncText) -> Self {
+        text.into_kind()
+    }
+}
+
+/// Copies the pages of `text`.
+impl From<&Text> for SyncText {
+    fn from(text: &Text) -> Self {
+        text.clone().into_kind()
+    }
+}
+
+/// Copies the pages of `text`.
+impl From<&SyncText> for Text {
+    fn from(text: &SyncText) -> Self {
+        text.clone().into_kind()
+    }
+}
@@ -0,0 +1,1526 @@
+use super::*;
+use crate::eol::EolSet;
+use crate::LineMeta;
+use crate::Offset;
+
+test_files!("../../../../");
+
+const SIZES: &[usize] = &[1, 2, 3, 4, 5, 7, 64, 8 * 1024];
+
+#[test]
+fn round_trip() {
+    let strs = [
+        "",
+        "\n",
+        "\r",
+        "\r\n",
+        "\n\n\r\r\n\r",
+        "     ",
+        "11\r2\n",
+        "  Hello, world\n   How you doing?  34    5678901234567890\n",
+        " \u{301}\n  🦀\n",
+        "\t\tfoo\n\t  bar\n  \tbaz\n\t\u{301}\n\t",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        for &size in SIZES {
+            test_round_trip(str, size);
+        }
+    }
+}
+
+fn test_round_trip(str: &str, size: usize) {
+    let mut builder = TextBuilder::default();
+
+    for chunk in chunks(str, size) {
+        builder.push(chunk);
+    }
+
+    let text = builder.done();
+    let mut offset = Offset::default();
+
+    for (page_offset, page) in &text.pages {
+        assert!(page_offset.len == offset.len);
+        assert!(page_offset.chars == offset.chars);
+        assert!(page_offset.utf16 == offset.utf16);
+        assert!(page_offset.lines == offset.lines);
+
+        offset.len += page.len as usize;
+        offset.chars += page.chars as usize;
+        offset.utf16 += page.utf16 as usize;
+        offset.lines += page.lines as usize;
+    }
+
+    assert!(text.to_string() == str, "{:?} (size {})", str, size);
+    assert!(text.len == str.len());
+    assert!(text.chars == str.chars().count());
+    assert!(text.utf16 == str.encode_utf16().count());
+    assert!(text.lines == offset.lines);
+    assert!(text.len == offset.len);
+    assert!(text.chars == offset.chars);
+}
+
+/// Splits `str` in chunks of at most `size` bytes, on `char` boundaries.
+fn chunks(mut str: &str, size: usize) -> impl '_ + Iterator<Item = &str> {
+    std::iter::from_fn(move || {
+        if str.is_empty() {
+            None
+        } else {
+            let mut at = size.min(str.len());
+            while !str.is_char_boundary(at) {
+                at += 1;
+            }
+
+            let (chunk, rest) = str.split_at(at);
+            str = rest;
+            Some(chunk)
+        }
+    })
+}
+
+#[test]
+fn read() {
+    for str in FILES {
+        for &size in SIZES {
+            let text = Text::read(Chunked::new(str.as_bytes(), size)).expect("be valid");
+            assert!(text.to_string() == *str);
+        }
+    }
+
+    for size in 1..=4 {
+        let text = Text::read(Chunked::new(b"a\r\nb\r", size)).expect("be valid");
+        assert!(text.to_string() == "a\r\nb\r");
+        assert!(text.lines == 2);
+
+        let err = Text::read(Chunked::new(b"ab\n\xF0\x9F\xA6", size));
+        assert!(matches!(err, Err(ReadError::Utf8 { offset: 3 })));
+
+        let err = Text::read(Chunked::new(b"ab\n\xF0\x9Fc", size));
+        assert!(matches!(err, Err(ReadError::Utf8 { offset: 3 })));
+    }
+
+    let long = format!("a\n {}\n", "bé".repeat(LineMeta::LEN_MAX as usize));
+    let text = Text::read(Chunked::new(long.as_bytes(), 64)).unwrap();
+    let line = text.line(1).unwrap();
+    assert!(text.to_string() == long);
+    assert!(line.spaces() == 1);
+    assert!(line.len() == long.len() - 4);
+    assert!(line.content().collect::<String>() == long[3..long.len() - 1]);
+}
+
+#[test]
+fn long_line() {
+    // Pushed in one piece: each page step must only look at the room left in
+    // the page, else this is quadratic in the line length
+    let line = "bé🦀\t".repeat(10_000);
+    let mut string = format!("  {}\n{}", line, line);
+    let mut text = Text::from(string.as_str());
+    assert!(text.to_string() == string);
+    assert!(text.lines == 1);
+    assert!(text.line(0).unwrap().spaces() == 2);
+    assert!(text.line(0).unwrap().len() == line.len());
+
+    // Content `'\r'`s, some of them followed by `'\n'`
+    let str = format!("{}\r\n", "bé🦀\r".repeat(10_000));
+    let mut builder = TextBuilder::with_eols(EolSet::LF_CRLF);
+    builder.push(&str);
+    let crs = builder.done();
+    assert!(crs.to_string() == str);
+    assert!(crs.lines == 1);
+
+    let at = (string.len() / 3..)
+        .find(|&i| string.is_char_boundary(i))
+        .unwrap();
+    text.insert(at, "x");
+    string.insert(at, 'x');
+    assert!(text.to_string() == string);
+    assert!(text.len == string.len());
+    assert!(text.chars == string.chars().count());
+}
+
+/// A reader returning at most `size` bytes per read.
+struct Chunked<'a> {
+    bytes: &'a [u8],
+    size:  usize,
+}
+
+impl<'a> Chunked<'a> {
+    fn new(bytes: &'a [u8], size: usize) -> Self {
+        Self { bytes, size }
+    }
+}
+
+impl<'a> Read for Chunked<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.size.min(buf.len()).min(self.bytes.len());
+        let (bytes, rest) = self.bytes.split_at(len);
+
+        buf[..len].copy_from_slice(bytes);
+        self.bytes = rest;
+        Ok(len)
+    }
+}
+
+#[test]
+fn lines() {
+    let strs = [
+        "",
+        "\n",
+        "a",
+        "a\n",
+        "  a\r\n\n   b  c\u{2028}d",
+        "    ",
+        " \u{301}\n",
+        "\t\ta\n\t \tb\n \tc\n\t\u{301}",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+        let expected = split_lines(str);
+
+        assert!(text.lines().len() == expected.len());
+        assert!(text.line(expected.len()).is_none());
+
+        let mut offset = 0;
+        for (i, (line, expected)) in text.lines().zip(&expected).enumerate() {
+            assert!(line.index() == i);
+            assert!(line.offset() == offset);
+            assert!(line.chunks().collect::<String>() == *expected);
+            assert!(expected.starts_with(line.indent()));
+            assert!(line.indent().len() == (line.spaces() + line.tabs()) as usize);
+
+            let content = line.content().collect::<String>();
+            assert!(content.len() == line.len());
+            assert!(content.chars().count() == line.chars());
+            assert!(line.as_str().is_none_or(|str| str == content));
+
+            let other = text.line(i).expect("line");
+            assert!(other.offset() == line.offset());
+            assert!(other.char_offset() == line.char_offset());
+            assert!(other.chunks().eq(line.chunks()));
+
+            offset += expected.len();
+        }
+
+        for start in 0..expected.len() {
+            let lines = text.lines_range(start..).expect("valid range");
+            assert!(lines
+                .map(|line| line.chunks().collect::<String>())
+                .eq(expected[start..].iter().cloned()));
+        }
+        assert!(text.lines_range(expected.len() + 1..).is_none());
+    }
+
+    let text = Text::from("\t\ta\n  b\n\t c");
+    let indents = text.lines().map(|line| (line.spaces(), line.tabs()));
+    assert!(indents.eq([(0, 2), (2, 0), (0, 1)]));
+}
+
+/// Splits `str` in lines, with their eols.
+fn split_lines(str: &str) -> Vec<String> {
+    split_lines_with(str, EolSet::ALL)
+}
+
+/// Splits `str` in lines, with their eols (of `eols`).
+fn split_lines_with(mut str: &str, eols: EolSet) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    loop {
+        match eols.split(str) {
+            (line, Some((eol, rest))) => {
+                lines.push(format!("{}{}", line, eol.as_str()));
+                str = rest;
+            }
+            (line, None) => {
+                lines.push(line.to_owned());
+                return lines;
+            }
+        }
+    }
+}
+
+#[test]
+fn positions() {
+    let strs = [
+        "",
+        "\n",
+        "a\r\nb",
+        "\r\n\r\n",
+        "é🦀\r\nb",
+        "  a\r\n\n   b  c\u{2028}d\u{85}",
+        " \u{301}\n  🦀\n",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+        let lines = split_lines(str);
+        let (mut byte, mut char) = (0, 0);
+
+        for (i, line) in lines.iter().enumerate() {
+            let (content, _) = Eol::split(line);
+
+            for (column, (index, _)) in line.char_indices().chain([(line.len(), ' ')]).enumerate() {
+                let last = i == lines.len() - 1;
+                if index == line.len() && !last {
+                    break;
+                }
+
+                let position = Position::new(i, index);
+                assert!(text.byte_to_position(byte + index) == Some(position));
+                assert!(text.char_to_position(char + column) == Some(Position::new(i, column)));
+
+                let utf16 = Position::new(i, line[..index].encode_utf16().count());
+                assert!(text.byte_to_utf16_position(byte + index) == Some(utf16));
+
+                // The middle of a `"\r\n"` is one column past the end
+                let in_crlf = index == content.len() + 1 && line.ends_with("\r\n");
+
+                if index <= content.len() || in_crlf {
+                    assert!(text.position_to_byte(position) == Some(byte + index));
+                    assert!(text.utf16_position_to_byte(utf16) == Some(byte + index));
+                    assert!(text.position_to_char(Position::new(i, column)) == Some(char + column));
+                } else {
+                    assert!(text.position_to_byte(position).is_none());
+                }
+            }
+
+            for index in 1..line.len() {
+                if !line.is_char_boundary(index) {
+                    assert!(text.byte_to_position(byte + index).is_none());
+                }
+            }
+
+            for (index, char) in content.char_indices() {
+                if char.len_utf16() == 2 {
+                    let column = line[..index].encode_utf16().count() + 1;
+                    assert!(text
+                        .utf16_position_to_byte(Position::new(i, column))
+                        .is_none());
+                }
+            }
+
+            byte += line.len();
+            char += line.chars().count();
+        }
+
+        assert!(text.byte_to_position(str.len() + 1).is_none());
+        assert!(text.char_to_position(str.chars().count() + 1).is_none());
+        assert!(text
+            .position_to_byte(Position::new(lines.len(), 0))
+            .is_none());
+    }
+}
+
+#[test]
+fn edits() {
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+    let inserts = [
+        "",
+        "a",
+        " ",
+        "  b",
+        "\n",
+        "\r",
+        "\r\n",
+        "🦀",
+        " \u{301}",
+        "x\ny\r\n  z",
+    ];
+
+    for str in FILES.iter().chain(&["", "\r\n", "a\rb\n\n  c"]) {
+        let mut text = Text::from(*str);
+        let mut string = str.to_string();
+
+        for _ in 0..200 {
+            // Measured, for edits to mark their pages unmeasured
+            text.pages.measure();
+            let snapshot = text.clone();
+            let before = string.clone();
+
+            let start = rng.boundary(&string);
+            let end = start + rng.boundary(&string[start..]);
+            let insert = inserts[rng.next() % inserts.len()];
+
+            match rng.next() % 3 {
+                0 => {
+                    text.insert(start, insert);
+                    string.insert_str(start, insert);
+                }
+                1 => {
+                    text.remove(start..end);
+                    string.replace_range(start..end, "");
+                }
+                _ => {
+                    text.replace_range(start..end, insert);
+                    string.replace_range(start..end, insert);
+                }
+            }
+
+            assert_text(&text, &string);
+            assert!(snapshot.to_string() == before);
+        }
+
+        // Batches of sorted edits
+        for _ in 0..20 {
+            text.pages.measure();
+            let snapshot = text.clone();
+            let before = string.clone();
+            let mut edits = Vec::new();
+            let mut start = 0;
+
+            while start < string.len() && edits.len() < 8 {
+                let from = start + rng.boundary(&string[start..]) / 2;
+                let from = (from..).find(|&i| string.is_char_boundary(i)).unwrap();
+                let to = from + rng.boundary(&string[from..]) / 4;
+                let to = (to..).find(|&i| string.is_char_boundary(i)).unwrap();
+
+                edits.push(Edit {
+                    range: from..to,
+                    str:   inserts[rng.next() % inserts.len()].to_owned(),
+                });
+                start = to + string[to..].chars().next().map_or(1, char::len_utf8);
+            }
+
+            text.replace_ranges(&edits);
+            for edit in edits.iter().rev() {
+                string.replace_range(edit.range.clone(), &edit.str);
+            }
+
+            assert_text(&text, &string);
+            assert!(snapshot.to_string() == before);
+        }
+    }
+}
+
+/// Asserts that `text` is `str`, built as by `Text::from` (with
+/// `text.eols`).
+fn assert_text<P: PointerKind>(text: &TextIn<P>, str: &str) {
+    let mut builder = TextBuilder::with_eols(text.eols);
+    builder.push(str);
+    let expected = builder.done();
+
+    assert!(text.to_string() == str);
+    assert!(text.len == expected.len);
+    assert!(text.chars == expected.chars);
+    assert!(text.utf16 == expected.utf16);
+    assert!(text.lines == expected.lines);
+
+    let mut offset = Offset::default();
+    for (page_offset, page) in &text.pages {
+        assert!(page.len != 0);
+        assert!(page_offset.len == offset.len);
+        assert!(page_offset.lines == offset.lines);
+        offset += page.size();
+
+        for record in page.records(0) {
+            assert!(record.meta.ascii == record.str.bytes().all(|b| (b' '..=b'~').contains(&b)));
+        }
+    }
+    let max_width = text.lines().map(|line| line.width()).max().unwrap();
+    assert!(text.clone().max_width() == max_width);
+
+    assert!(text.lines().len() == expected.lines().len());
+    for (a, b) in text.lines().zip(expected.lines()) {
+        assert!(a.indent() == b.indent());
+        assert!(a.eol() == b.eol());
+        assert!(a.content().collect::<String>() == b.content().collect::<String>());
+    }
+}
+
+/// A xorshift pseudo-random number generator.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    /// Returns a random char boundary of `str`.
+    fn boundary(&mut self, str: &str) -> usize {
+        let mut i = self.next() % (str.len() + 1);
+        while !str.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    }
+}
+
+#[test]
+fn undo() {
+    use std::collections::HashSet;
+
+    // Pages of `text` not in `other`
+    let unique = |text: &Text, other: &Text| {
+        let shared = other
+            .pages
+            .iter()
+            .map(|(_, page)| page.bytes.as_ptr())
+            .collect::<HashSet<_>>();
+        text.pages
+            .iter()
+            .filter(|(_, page)| !shared.contains(&page.bytes.as_ptr()))
+            .count()
+    };
+    let cost = std::mem::size_of::<[u8; page::BYTES]>() + std::mem::size_of::<RcPage>();
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+
+    for str in FILES {
+        let mut text = Text::from(*str);
+        let mut history = UndoStack::new(usize::MAX);
+        let mut states = vec![str.to_string()];
+        let mut used = 0;
+
+        for _ in 0..50 {
+            let string = states.last().unwrap();
+            let start = rng.boundary(string);
+            let end = start + rng.boundary(&string[start..]);
+            let mut string = string.clone();
+            let snapshot = text.snapshot();
+
+            string.replace_range(start..end, "ab\n");
+            history.edit(&mut text, start..end, "ab\n");
+            states.push(string);
+
+            used += unique(&snapshot, &text) * cost + 3;
+            assert!(history.used() == used);
+        }
+
+        for state in states.iter().rev().skip(1) {
+            assert!(history.undo(&mut text).is_some());
+            assert_text(&text, state);
+        }
+        assert!(history.undo(&mut text).is_none());
+
+        for state in states.iter().skip(1) {
+            assert!(history.redo(&mut text).is_some());
+            assert_text(&text, state);
+        }
+        assert!(history.redo(&mut text).is_none());
+        assert!(history.used() == used);
+
+        history.undo(&mut text);
+        history.edit(&mut text, 0..0, "new");
+        assert!(!history.can_redo());
+    }
+
+    let mut text = Text::from("Hello, world!");
+    let mut history = UndoStack::new(0);
+    history.edit(&mut text, 0..5, "Bye");
+    assert!(!history.can_undo());
+    assert!(history.used() == 0);
+}
+
+#[test]
+fn pages() {
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+    let all = Text::from(FILES.concat().as_str())
+        .pages
+        .iter()
+        .map(|(_, page)| page.clone())
+        .collect::<Vec<_>>();
+
+    let mut pages = Pages::default();
+    let mut expected = Vec::<RcPage>::new();
+
+    for _ in 0..500 {
+        let start = rng.next() % (expected.len() + 1);
+        let end = start + rng.next() % (expected.len() - start + 1).min(8);
+        let from = rng.next() % all.len();
+        let new = &all[from..all.len().min(from + rng.next() % 8)];
+
+        let snapshot = pages.clone();
+        let before = expected.clone();
+
+        pages.splice(start..end, new.iter().cloned());
+        expected.splice(start..end, new.iter().cloned());
+
+        assert_pages(&pages, &expected);
+        assert_pages(&snapshot, &before);
+    }
+}
+
+/// Asserts that `pages` holds `expected`, with consistent offsets.
+fn assert_pages(pages: &Pages, expected: &[RcPage]) {
+    let mut offset = Offset::default();
+
+    assert!(pages.len() == expected.len());
+    assert!(pages.iter().len() == expected.len());
+
+    for (i, ((page_offset, page), expected)) in pages.iter().zip(expected).enumerate() {
+        assert!(std::ptr::eq(&page.bytes[..], &expected.bytes[..]));
+        assert!(page_offset.len == offset.len);
+        assert!(page_offset.chars == offset.chars);
+        assert!(page_offset.utf16 == offset.utf16);
+        assert!(page_offset.lines == offset.lines);
+
+        let (get_offset, get) = pages.get(i).unwrap();
+        assert!(std::ptr::eq(get, page) && get_offset.len == offset.len);
+
+        offset += page.size();
+        assert!(pages.partition_point(|end| end.chars < offset.chars) <= i);
+        assert!(pages.partition_point(|end| end.chars <= offset.chars) > i);
+    }
+
+    assert!(pages.get(expected.len()).is_none());
+    assert!(pages.size().len == offset.len);
+    assert!(pages.size().lines == offset.lines);
+}
+
+#[test]
+fn sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SyncText>();
+
+    for str in FILES {
+        let text = Text::from(*str);
+        let snapshot = text.snapshot();
+        let mut sync = SyncText::from(text);
+        let sync = std::thread::spawn(move || {
+            assert_text(&sync, str);
+            sync.insert(0, "a\n");
+            sync
+        })
+        .join()
+        .unwrap();
+
+        assert_text(&snapshot, str);
+        assert_text(&Text::from(&sync), &format!("a\n{str}"));
+        assert_text(&Text::from(sync), &format!("a\n{str}"));
+        assert_text(&SyncText::read(str.as_bytes()).unwrap(), str);
+    }
+}
+
+#[test]
+fn indent() {
+    let text = Text::from("a\n    b\n\t\tc\n \u{301}\n\t  d\n");
+    let indents = (0..=5).map(|line| text.indent_of(line).unwrap());
+    assert!(indents.eq([
+        Indent::Spaces(0),
+        Indent::Spaces(4),
+        Indent::Tabs(2),
+        Indent::Spaces(1),
+        Indent::Tabs(1),
+        Indent::Spaces(0),
+    ]));
+    assert!(text.indent_of(6).is_none());
+
+    let style = |str| Text::from(str).detect_indent_style();
+    assert!(style("a\n  b\n    c\n  d\n      e\n") == Some(IndentStyle::Spaces(2)));
+    assert!(style("a\n\tb\n\t\tc\n    d\n") == Some(IndentStyle::Tabs));
+    assert!(style("a\nb\n").is_none());
+
+    let mut rng = Rng(0x853C49E6748FEA9B);
+    let strs = ["a\n  b\n\n   \n\t\tc\n \u{301}\n\t  d"];
+
+    for str in strs.iter().chain(FILES) {
+        let mut text = Text::from(*str);
+        let mut string = str.to_string();
+
+        for _ in 0..20 {
+            let start = rng.next() % (text.lines + 1);
+            let end = start + rng.next() % (text.lines + 2 - start);
+            let delta = (rng.next() % 9) as isize - 4;
+            let style = text.detect_indent_style();
+            let tabs = style == Some(IndentStyle::Tabs);
+            let snapshot = text.snapshot();
+            let before = string.clone();
+
+            text.reindent(start..end, delta, style);
+            string = reindent(&string, start..end, delta, tabs);
+
+            assert_text(&text, &string);
+            assert!(snapshot.to_string() == before);
+        }
+    }
+
+    // Overflowing `LineMeta::SPACES_MAX`
+    let mut text = Text::from("a\n b\n");
+    text.reindent(.., 100, None);
+    assert_text(
+        &text,
+        &format!("{}a\n{}b\n", " ".repeat(100), " ".repeat(101)),
+    );
+
+    // Dedenting unindented lines leaves pages shared
+    let mut text = Text::from("\ta\nb\n");
+    let snapshot = text.snapshot();
+    text.reindent(1.., -1, Some(IndentStyle::Tabs));
+    assert_text(&text, "\ta\nb\n");
+    for ((_, page), (_, other)) in text.pages.iter().zip(snapshot.pages.iter()) {
+        assert!(page.bytes.ptr_eq(&other.bytes));
+    }
+}
+
+/// Reindents lines in `range` of `str`, as by `Text::reindent`.
+fn reindent(str: &str, range: std::ops::Range<usize>, delta: isize, tabs: bool) -> String {
+    let mut string = String::new();
+
+    for (i, line) in split_lines(str).iter().enumerate() {
+        let space = match line.chars().next() {
+            Some(char @ (' ' | '\t')) => char,
+            _ if tabs => '\t',
+            _ => ' ',
+        };
+        let before = line.bytes().take_while(|&byte| byte == space as u8).count();
+        let (content, _) = Eol::split(&line[before..]);
+
+        if !range.contains(&i) || (delta > 0 && content.is_empty()) {
+            string.push_str(line);
+        } else {
+            let after = before.saturating_add_signed(delta);
+            string.push_str(&space.to_string().repeat(after));
+            string.push_str(&line[before..]);
+        }
+    }
+
+    string
+}
+
+#[test]
+fn eols() {
+    let text = Text::from("a\r\nb\nc\r\nd\u{2028}\r\n");
+    let stats = text.eol_stats();
+    assert!(stats.count(Eol::CRLF) == 3);
+    assert!(stats.count(Eol::LF) == 1);
+    assert!(stats.count(Eol::CR) == 0);
+    assert!(stats.total() == 5);
+    assert!(stats.is_mixed());
+    assert!(text.dominant_eol() == Some(Eol::CRLF));
+
+    assert!(Text::from("a\rb\nc").dominant_eol() == Some(Eol::LF));
+    assert!(!Text::from("a\nb\n").eol_stats().is_mixed());
+    assert!(Text::from("a").dominant_eol().is_none());
+
+    let strs = ["a\r\n\n\r\r\n\u{85} b\u{2028}\n"];
+    for str in strs.iter().chain(FILES) {
+        for eol in Eol::ALL {
+            let mut text = Text::from(*str);
+            let snapshot = text.snapshot();
+            let expected = split_lines(str)
+                .iter()
+                .map(|line| match Eol::split(line) {
+                    (line, Some(_)) => format!("{}{}", line, eol.as_str()),
+                    (line, None) => line.to_owned(),
+                })
+                .collect::<String>();
+
+            text.normalize_eols(eol);
+            assert_text(&text, &expected);
+            assert!(snapshot.to_string() == *str);
+            assert!(text.eol_stats().count(eol) == text.lines);
+            assert!(!text.eol_stats().is_mixed());
+        }
+    }
+}
+
+#[test]
+fn normalize_eols() {
+    let sets = [
+        EolSet::ALL,
+        EolSet::LF_CRLF,
+        EolSet::new(&[Eol::CR, Eol::CRLF]),
+        EolSet::new(&[Eol::LF, Eol::CR]),
+        EolSet::new(&[Eol::CRLF, Eol::NEL]),
+    ];
+    let parts = [
+        "a", "é", " ", "\t", "\r", "\n", "\r\n", "\u{85}", "\u{2028}", "\x0C",
+    ];
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+
+    for eols in sets {
+        for _ in 0..200 {
+            let str = (0..rng.next() % 16)
+                .map(|_| parts[rng.next() % parts.len()])
+                .collect::<String>();
+
+            for eol in eols.iter() {
+                let mut builder = TextBuilder::with_eols(eols);
+                builder.push(&str);
+                let mut text = builder.done();
+                text.normalize_eols(eol);
+
+                // As a fresh build of the normalized text
+                let string = text.to_string();
+                let mut builder = TextBuilder::with_eols(eols);
+                builder.push(&string);
+
+                assert_text(&text, &string);
+                assert!(text.eol_stats() == builder.done().eol_stats());
+            }
+        }
+    }
+
+    let mut builder = TextBuilder::with_eols(EolSet::LF_CRLF);
+    builder.push("  \u{85}\u{85}\r\r\n");
+    let mut text = builder.done();
+    text.normalize_eols(Eol::LF);
+    assert!(text.to_string() == "  \u{85}\u{85}\r\n");
+    assert!(text.line(0).unwrap().eol() == Some(Eol::CRLF));
+    assert!(text.max_width() == 4);
+}
+
+#[test]
+fn eol_sets() {
+    let sets = [
+        EolSet::ALL,
+        EolSet::EMPTY,
+        EolSet::LF_CRLF,
+        EolSet::from(Eol::CR),
+        EolSet::from(Eol::CRLF),
+        EolSet::new(&[Eol::LF, Eol::CR]),
+        EolSet::new(&[Eol::NEL, Eol::LS, Eol::PS]),
+    ];
+    let strs = [
+        "a\r\nb\rc\nd\x0Be\x0Cf\u{85}g\u{2028}h\u{2029}",
+        "\r\r\n\n\r  \r\n\x0C\n\r",
+        ";; \x0C\n(defun f ()\r\n  \x0C)\r",
+    ];
+
+    for eols in sets {
+        for str in strs.iter().chain(FILES) {
+            let lines = split_lines_with(str, eols);
+
+            for &size in SIZES {
+                let mut builder = TextBuilder::with_eols(eols);
+                chunks(str, size).for_each(|chunk| builder.push(chunk));
+                let text = builder.done();
+
+                assert!(text.eols == eols);
+                assert!(text.to_string() == *str);
+                assert!(text.lines + 1 == lines.len(), "{:?} {:?}", eols, str);
+
+                for (line, expected) in text.lines().zip(&lines) {
+                    let (content, eol) = eols.split(expected);
+                    assert!(line.eol() == eol.map(|(eol, _)| eol));
+                    assert!(
+                        line.indent().to_string() + &line.content().collect::<String>() == content
+                    );
+                }
+            }
+        }
+    }
+
+    // Edits keep recognizing the same eols
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+    let inserts = ["\r", "\n", "\r\n", "\x0C", "a\rb", " \u{2028}"];
+
+    for eols in sets {
+        let mut builder = TextBuilder::with_eols(eols);
+        builder.push(strs[2]);
+        let mut text = builder.done();
+        let mut string = strs[2].to_string();
+
+        for _ in 0..100 {
+            let start = rng.boundary(&string);
+            let end = start + rng.boundary(&string[start..]);
+            let insert = inserts[rng.next() % inserts.len()];
+
+            text.replace_range(start..end, insert);
+            string.replace_range(start..end, insert);
+            assert_text(&text, &string);
+        }
+    }
+}
+
+#[test]
+fn clusters() {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let strs = [
+        "  e\u{301}\r\n \u{301}\u{302}\n\t\u{1F1EB}\u{1F1F7}\u{1F1EE}",
+        "abcdefghijklmnopqrstuvwxyz\u{301}\u{1F469}\u{200D}\u{1F52C}",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+        let clusters = text.clusters().collect::<Vec<_>>();
+        let expected = str.grapheme_indices(true).collect::<Vec<_>>();
+
+        assert!(clusters.len() == expected.len());
+        for ((i, cluster), &(j, expected)) in clusters.iter().zip(&expected) {
+            assert!(*i == j);
+            assert!(cluster == expected);
+        }
+    }
+}
+
+#[test]
+fn motions() {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    fn class(char: char) -> u8 {
+        if char.is_whitespace() {
+            0
+        } else if char.is_alphanumeric() || char == '_' {
+            1
+        } else {
+            2
+        }
+    }
+
+    let strs = [
+        "",
+        "\r\n",
+        "  fn main() {\r\n    e\u{301}\r\n}",
+        "\t\t foo.bar(baz)  \n\n  \u{301}x\r\n \u{1F469}\u{200D}\u{1F52C}!!\u{2028}a_b",
+        "          long  indentation\n                     ",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+        let mut boundaries = str
+            .grapheme_indices(true)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        boundaries.push(str.len());
+        let clusters = str
+            .grapheme_indices(true)
+            .map(|(i, cluster)| (i, class(cluster.chars().next().unwrap())))
+            .collect::<Vec<_>>();
+
+        for byte in (0..=str.len()).filter(|&i| str.is_char_boundary(i)) {
+            let next = *boundaries.iter().find(|&&i| i > byte).unwrap_or(&str.len());
+            let prev = *boundaries.iter().rev().find(|&&i| i < byte).unwrap_or(&0);
+            assert!(text.next_grapheme(byte) == Some(next), "{:?} {}", str, byte);
+            assert!(text.prev_grapheme(byte) == Some(prev), "{:?} {}", str, byte);
+
+            // Words, from clusters
+            let i = clusters.partition_point(|&(i, _)| i < byte);
+            let containing = clusters
+                .partition_point(|&(i, _)| i <= byte)
+                .saturating_sub(1);
+            let next_word = clusters
+                .iter()
+                .enumerate()
+                .skip(containing + 1)
+                .find(|&(j, &(_, class))| class != 0 && class != clusters[j - 1].1)
+                .map_or(str.len(), |(_, &(start, _))| start);
+            let mut prev_word = byte;
+            let mut current = 0;
+            for &(start, class) in clusters[..i].iter().rev() {
+                if current != 0 && class != current {
+                    break;
+                }
+                current = class;
+                prev_word = start;
+            }
+            assert!(
+                text.next_word(byte) == Some(next_word),
+                "{:?} {}",
+                str,
+                byte
+            );
+            assert!(
+                text.prev_word(byte) == Some(prev_word),
+                "{:?} {}",
+                str,
+                byte
+            );
+
+            // Lines
+            let start = split_lines(str)
+                .iter()
+                .scan(0, |end, line| {
+                    *end += line.len();
+                    Some((*end - line.len(), line.clone()))
+                })
+                .filter(|(start, _)| *start <= byte)
+                .last()
+                .unwrap();
+            let (content, _) = Eol::split(&start.1);
+            let blanks = content.len() - content.trim_start_matches([' ', '\t']).len();
+            let (line_start, line_end) = (start.0, start.0 + content.len());
+            assert!(text.line_start_of(byte) == Some(line_start));
+            assert!(text.line_end_of(byte) == Some(line_end));
+            assert!(text.first_non_blank(byte) == Some(line_start + blanks));
+        }
+
+        assert!(text.next_grapheme(str.len() + 1).is_none());
+        assert!(text.next_word(str.len() + 1).is_none());
+    }
+}
+
+#[test]
+fn widths() {
+    use unicode_segmentation::UnicodeSegmentation;
+    use unicode_width::UnicodeWidthStr;
+
+    let options = [
+        WidthOptions::default(),
+        WidthOptions {
+            tab_width:      4,
+            ambiguous_wide: true,
+            zero_width:     1,
+        },
+        WidthOptions {
+            tab_width: 1,
+            ..Default::default()
+        },
+    ];
+    let strs = [
+        "\tlet 猫 = \"🐈\";\r\n  a\tb\u{200B}c\n\u{1F469}\u{200D}\u{1F52C} α° e\u{301}",
+        "\t\t  \tx\t\t\u{7}\n",
+        "\t\tfoo(bar);\n    baz\n\n  \n",
+    ];
+
+    for &option in &options {
+        for str in strs.iter().chain(FILES) {
+            let mut text = Text::from(*str);
+            text.set_width_options(option);
+            let mut max_width = 0;
+
+            for (line, expected) in text.lines().zip(split_lines(str)) {
+                let (content, _) = Eol::split(&expected);
+                let mut columns = Vec::new();
+                let mut column = 0;
+
+                for (i, cluster) in content.grapheme_indices(true) {
+                    let width = if cluster == "\t" {
+                        option.tab_width - column % option.tab_width
+                    } else {
+                        let width = if option.ambiguous_wide {
+                            cluster.width_cjk()
+                        } else {
+                            cluster.width()
+                        };
+                        if width == 0 {
+                            option.zero_width
+                        } else {
+                            width.min(2)
+                        }
+                    };
+
+                    assert!(option.width(cluster, column) == width);
+                    columns.push((i, cluster.len(), column, width));
+                    column += width;
+                }
+
+                assert!(line.width() == column);
+                max_width = max_width.max(column);
+
+                for byte in 0..=content.len() + 1 {
+                    let expected = if byte > content.len() || !content.is_char_boundary(byte) {
+                        None
+                    } else {
+                        Some(
+                            columns
+                                .iter()
+                                .find(|&&(i, len, ..)| byte < i + len)
+                                .map_or(column, |&(_, _, column, _)| column),
+                        )
+                    };
+                    assert!(
+                        line.byte_to_column(byte) == expected,
+                        "{:?} {}",
+                        content,
+                        byte
+                    );
+                }
+
+                for col in 0..=column + 1 {
+                    let expected = columns
+                        .iter()
+                        .find(|&&(.., column, width)| col < column + width)
+                        .map_or(content.len(), |&(i, ..)| i);
+                    assert!(
+                        line.column_to_byte(col) == expected,
+                        "{:?} {}",
+                        content,
+                        col
+                    );
+                }
+            }
+
+            assert!(text.max_width() == max_width);
+        }
+    }
+
+    // Loading and editing do not measure pages, only `max_width` does
+    let mut text = Text::from("猫\tα°\n".repeat(100).as_str());
+    assert!(text.pages.unmeasured() == text.pages.len());
+    assert!(text.max_width() == 10);
+    assert!(text.pages.unmeasured() == 0);
+
+    text.insert(text.len / 2, "猫猫猫");
+    // Only the (10 bytes) pages around the edit
+    assert!((1..10).contains(&text.pages.unmeasured()));
+    assert!(text.max_width() == 18);
+    assert!(text.pages.unmeasured() == 0);
+}
+
+#[test]
+fn wrap() {
+    let strs = [
+        "fn main() { let x = 1; }\n    foo bar baz\r\n\tlong_identifier_without_spaces\n",
+        "猫猫猫 🐈🐈 e\u{301}e\u{301}e\u{301}  \n          deep  indentation here\n\n",
+    ];
+    let mut options = Vec::new();
+    for width in [0, 1, 2, 5, 12, 40] {
+        for (words, indent) in [(false, false), (true, false), (true, true)] {
+            options.push(WrapOptions {
+                width,
+                words,
+                indent,
+            });
+        }
+    }
+
+    for &options in &options {
+        for str in strs.iter().chain(FILES) {
+            let text = Text::from(*str);
+            let wrap = SoftWrap::new(&text, options);
+            let rows = (0..wrap.rows())
+                .map(|row| wrap.row(row).unwrap())
+                .collect::<Vec<_>>();
+            assert!(wrap.row(wrap.rows()).is_none());
+
+            for line in text.lines() {
+                let range = wrap.line_rows(line.index()).unwrap();
+                let rows = &rows[range.clone()];
+                let columns = line.columns().collect::<Vec<_>>();
+
+                // Rows cover the line
+                assert!(rows.iter().all(|row| row.line == line.index()));
+                assert!(rows[0].range.start == 0);
+                assert!(rows.last().unwrap().range.end == line.indent().len() + line.len());
+                assert!(rows
+                    .windows(2)
+                    .all(|rows| rows[0].range.end == rows[1].range.start));
+
+                // Rows fit, unless a single cluster
+                for row in rows {
+                    let clusters = columns
+                        .iter()
+                        .filter(|(range, ..)| row.range.contains(&range.start))
+                        .collect::<Vec<_>>();
+                    let width = clusters
+                        .iter()
+                        .map(|&&(_, _, width, _)| width)
+                        .sum::<usize>();
+
+                    assert!(
+                        options.width == 0
+                            || row.indent + width <= options.width
+                            || clusters.len() == 1
+                    );
+                }
+
+                // Round trips
+                for &(ref range, _, width, _) in &columns {
+                    if width == 0 {
+                        continue;
+                    }
+
+                    let (row, column) = wrap.to_visual(&text, line.index(), range.start).unwrap();
+                    assert!(
+                        wrap.from_visual(&text, row, column) == Some((line.index(), range.start))
+                    );
+                }
+            }
+        }
+    }
+
+    // Incremental layouts
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+    let inserts = ["", "a", "  ", "\n", "foo bar\n  baz qux quux", "🐈"];
+    let options = WrapOptions {
+        width: 7,
+        ..Default::default()
+    };
+
+    for str in strs.iter().chain(FILES) {
+        let mut text = Text::from(*str);
+        let mut wrap = SoftWrap::new(&text, options);
+        let mut string = str.to_string();
+
+        for _ in 0..100 {
+            let start = rng.boundary(&string);
+            let end = start + rng.boundary(&string[start..]);
+            let insert = inserts[rng.next() % inserts.len()];
+
+            let first = text.byte_to_position(start).unwrap().line;
+            let last = text.byte_to_position(end).unwrap().line;
+            text.replace_range(start..end, insert);
+            string.replace_range(start..end, insert);
+            let new_last = text.byte_to_position(start + insert.len()).unwrap().line;
+
+            wrap.splice(&text, first..last + 1, new_last + 1 - first);
+            let expected = SoftWrap::new(&text, options);
+            assert!(wrap.rows() == expected.rows());
+            assert!((0..wrap.rows()).all(|row| wrap.row(row) == expected.row(row)));
+        }
+    }
+}
+
+#[test]
+fn search() {
+    let strs = [
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "abababababab\nab\r\nabababa        abab\n    ba\n",
+        "    \n        \n\t\t  \n            x\n",
+    ];
+    let patterns = [
+        "a", "aa", "aba", "ab\n", "\nab", "    ", "  \n ", "\n\t", "{", "fn ",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+
+        for pattern in patterns {
+            let matches = text.find_iter(pattern).collect::<Vec<_>>();
+            let expected = str.match_indices(pattern).collect::<Vec<_>>();
+
+            assert!(matches.len() == expected.len());
+            for (found, &(i, _)) in matches.iter().zip(&expected) {
+                assert!(found.range == (i..i + pattern.len()));
+                assert!(Some(found.start) == text.byte_to_position(i));
+                assert!(Some(found.end) == text.byte_to_position(found.range.end));
+            }
+
+            let (replaced, edits) = text.replace_all(pattern, "<$0>");
+            assert_text(&replaced, &str.replace(pattern, "<$0>"));
+            assert!(edits
+                .iter()
+                .map(|edit| edit.range.clone())
+                .eq(matches.into_iter().map(|found| found.range)));
+            assert!(text.to_string() == *str);
+        }
+
+        assert!(text.find("").is_none());
+        assert!(text.replace_all("", "x").0.to_string() == *str);
+    }
+}
+
+#[test]
+fn write() {
+    use std::fmt::Write;
+    use FinalEol::*;
+
+    let mixed = "a  \r\n \t \n\u{2028}\u{3000}b\u{3000}\rc \r\n\r\n  ";
+    let indented = "                    x                    \n\t\t\t\t\t\t\t\t\t\ty";
+
+    for str in [mixed, indented].iter().chain(FILES) {
+        let text = Text::from(*str);
+
+        let mut string = String::new();
+        write!(string, "{}", text).unwrap();
+        assert!(string == *str);
+
+        let mut bytes = Vec::new();
+        text.write_to(&mut bytes).unwrap();
+        assert!(bytes == str.as_bytes());
+    }
+
+    // (text, trim, eol, final eol, expected)
+    let cases = [
+        ("", false, None, Ensure, ""),
+        ("", true, None, Strip, ""),
+        ("  \t", false, None, Ensure, "  \t\n"),
+        ("  \t", true, None, Ensure, ""),
+        ("a\r\nb", false, None, Ensure, "a\r\nb\r\n"),
+        ("a\nb", false, Some(Eol::PS), Ensure, "a\u{2029}b\u{2029}"),
+        ("a\n\n", false, None, Strip, "a\n"),
+        ("a\n   ", false, None, Strip, "a\n   "),
+        ("a\n   ", true, None, Keep, "a\n"),
+        ("a\n   ", true, None, Ensure, "a\n"),
+        ("a\n   ", true, None, Strip, "a"),
+        ("x \n\t\n", true, None, Strip, "x\n"),
+        (mixed, false, None, Keep, mixed),
+        (
+            mixed,
+            false,
+            Some(Eol::CRLF),
+            Strip,
+            "a  \r\n \t \r\n\r\n\u{3000}b\u{3000}\r\nc \r\n\r\n  ",
+        ),
+        (
+            mixed,
+            true,
+            None,
+            Keep,
+            "a\r\n\n\u{2028}\u{3000}b\rc\r\n\r\n",
+        ),
+        (mixed, true, Some(Eol::LF), Strip, "a\n\n\n\u{3000}b\nc\n"),
+        (
+            indented,
+            true,
+            Some(Eol::CRLF),
+            Ensure,
+            "                    x\r\n\t\t\t\t\t\t\t\t\t\ty\r\n",
+        ),
+    ];
+
+    for (str, trim, eol, final_eol, expected) in cases {
+        let mut bytes = Vec::new();
+        let options = WriteOptions {
+            eol,
+            trim,
+            final_eol,
+        };
+
+        Text::from(str).write_to_with(&mut bytes, options).unwrap();
+        assert!(bytes == expected.as_bytes(), "{:?} {:?}", str, options);
+    }
+}
+
+/// A fresh temporary directory, removed on drop.
+struct TempDir(std::path::PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("grappe-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+
+        Self(dir)
+    }
+
+    fn join(&self, path: &str) -> std::path::PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn save() {
+    let dir = TempDir::new("save");
+    let path = dir.join("file.txt");
+
+    // New file
+    let text = Text::from("a\r\nb\r\nc\n  ");
+    let options = SaveOptions {
+        write: WriteOptions {
+            trim: true,
+            final_eol: FinalEol::Ensure,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    text.save(&path, options).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "a\r\nb\r\nc\r\n");
+
+    // With a backup, keeping eols
+    let text = Text::from("x\ny\r\n");
+    let options = SaveOptions {
+        dominant_eol: false,
+        backup: true,
+        ..Default::default()
+    };
+    text.save(&path, options).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "x\ny\r\n");
+    assert!(std::fs::read_to_string(dir.join("file.txt~")).unwrap() == "a\r\nb\r\nc\r\n");
+
+    // Backups are replaced
+    let text = Text::from("");
+    text.save(&path, options).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "");
+    assert!(std::fs::read_to_string(dir.join("file.txt~")).unwrap() == "x\ny\r\n");
+
+    // The eol of the file, for inserted lines
+    std::fs::write(&path, "a\r\nb\r\n").unwrap();
+    let mut text = Text::open(&path).unwrap();
+    text.insert(0, "TODO\n");
+    text.insert(text.len, "c\nd");
+    assert!(text.file_eol == Some(Eol::CRLF));
+    text.save(&path, SaveOptions::default()).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "TODO\r\na\r\nb\r\nc\r\nd");
+
+    // Unless written as is
+    let as_is = SaveOptions {
+        dominant_eol: false,
+        ..Default::default()
+    };
+    text.save(&path, as_is).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "TODO\na\r\nb\r\nc\nd");
+
+    // The eol normalized to
+    std::fs::write(&path, "a\nb\n").unwrap();
+    let mut text = Text::open(&path).unwrap();
+    text.normalize_eols(Eol::CRLF);
+    text.save(&path, SaveOptions::default()).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "a\r\nb\r\n");
+
+    // The dominant eol of texts not read from a file
+    let text = Text::from("a\nb\r\nc\r\n");
+    text.save(&path, SaveOptions::default()).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "a\r\nb\r\nc\r\n");
+
+    // No temporary files left
+    assert!(std::fs::read_dir(&dir.0).unwrap().count() == 2);
+    assert!(text.save(dir.join("missing/file.txt"), options).is_err());
+    assert!(std::fs::read_dir(&dir.0).unwrap().count() == 2);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn search_regex() {
+    use regex_automata::meta;
+
+    let strs = [
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "abababababab\nab\r\nabababa        abab\n    ba\n",
+        "é\u{301}ü  \n\u{1F1EB}\u{1F1F7} x\n",
+    ];
+    let patterns = [
+        "a+",
+        "(ab)+",
+        "b?",
+        r"\s+",
+        r"(?m)^\s*",
+        r"(?m)\w+$",
+        r"(?-u:\b)ab",
+        "^a|a$",
+        r"[^\n]{3,9}\n",
+        r"(?-u:\b)\w+\(",
+        "",
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+
+        for pattern in patterns {
+            let regex = Regex::new(pattern).unwrap();
+            let matches = text.find_regex_iter(&regex).collect::<Vec<_>>();
+            let expected = meta::Regex::new(pattern)
+                .unwrap()
+                .find_iter(*str)
+                .map(|found| found.range())
+                .collect::<Vec<_>>();
+
+            assert!(matches.len() == expected.len());
+            for (found, range) in matches.iter().zip(expected) {
+                assert!(found.range == range);
+                assert!(Some(found.start) == text.byte_to_position(range.start));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn replace() {
+    use regex_automata::meta;
+
+    let strs = [
+        "abababababab\nab\r\nabababa        abab\n    ba\n",
+        "fn a() {\n    let b = 1;\n    let c = 22;\n}\n\n\n\n\n\n\n\nfn d() {}\n",
+    ];
+    let replacements = [
+        ("a+", "<$0>"),
+        ("(?<a>a)b", "${a}$$"),
+        (r"(?m)^(\s*)(\w+)", "$2\n$1"),
+        (r"(?m)\s+$", ""),
+        ("b?", "-"),
+        (r"let (\w+) = (\d+);", "const $1: u8 = $2;"),
+        (r"(?-u:\b)(\w)(\w*)\(", "$2$1("),
+    ];
+
+    for str in strs.iter().chain(FILES) {
+        let text = Text::from(*str);
+
+        for (pattern, replacement) in replacements {
+            let regex = Regex::new(pattern).unwrap();
+            let (replaced, edits) = text.replace_regex_all(&regex, replacement);
+
+            let mut expected = String::new();
+            let mut end = 0;
+            for captures in meta::Regex::new(pattern).unwrap().captures_iter(*str) {
+                let range = captures.get_match().unwrap().range();
+                expected.push_str(&str[end..range.start]);
+                captures.interpolate_string_into(str, replacement, &mut expected);
+                end = range.end;
+            }
+            expected.push_str(&str[end..]);
+
+            assert_text(&replaced, &expected);
+            assert!(text.to_string() == *str);
+
+            // Edits turn the text into the replaced one
+            let mut string = str.to_string();
+            for edit in edits.iter().rev() {
+                string.replace_range(edit.range.clone(), &edit.str);
+            }
+            assert!(string == expected);
+
+            // Pages before the line (or the one before) of the first match are
+            // shared
+            let first = edits.first().map_or(str.len(), |edit| edit.range.start);
+            let line = text.byte_to_position(first).unwrap().line.saturating_sub(1);
+            let (_, _, start) = text.line_start(line).unwrap();
+            for ((offset, page), (_, other)) in text.pages.iter().zip(replaced.pages.iter()) {
+                if offset.len + page.len as usize >= start.len {
+                    break;
+                }
+                assert!(page.bytes.ptr_eq(&other.bytes));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped() {
+    let dir = TempDir::new("mapped");
+    let path = dir.join("file.txt");
+
+    let long = "é\r\n  🦀 \t\u{2028}".repeat(100);
+    let strs = [
+        "",
+        "\r\n",
+        "a line without eol, but longer than a segment: ...",
+    ];
+
+    for str in strs.iter().chain(&[long.as_str()]).chain(FILES) {
+        std::fs::write(&path, str).unwrap();
+        // Safety: the file is not modified while mapped
+        let file = unsafe { MappedText::open(&path) }.unwrap();
+
+        assert!(file.as_str() == *str);
+        assert!(file.built() == 0);
+
+        // Segments start after eols
+        for index in 0..file.segments() {
+            let range = file.range(index).unwrap();
+            let text = file.segment(index).unwrap();
+
+            assert!(text.to_string() == str[range.clone()]);
+            assert!(file.built() == index + 1);
+
+            if let Some(previous) = index.checked_sub(1).and_then(|index| file.segment(index)) {
+                assert!(previous.line(previous.lines).unwrap().is_empty());
+            }
+        }
+
+        let text = file.to_text();
+        let expected = Text::from(*str);
+        assert!(text.to_string() == *str);
+        assert!(text.len == expected.len);
+        assert!(text.chars == expected.chars);
+        assert!(text.utf16 == expected.utf16);
+        assert!(text.lines == expected.lines);
+        assert!(text
+            .lines()
+            .map(|line| line.chunks().collect::<String>())
+            .eq(split_lines(str)));
+        assert!((0..str.len()).all(|offset| file.segment_at(offset).is_some()));
+        assert!(file.segment_at(str.len()).is_none());
+    }
+
+    // Invalid UTF-8, validated in parallel
+    let mut bytes = long.into_bytes();
+    bytes[1000] = 0xFF;
+    std::fs::write(&path, &bytes).unwrap();
+    let expected = std::str::from_utf8(&bytes).unwrap_err().valid_up_to();
+    assert!(matches!(
+        unsafe { MappedText::open(&path) },
+        Err(ReadError::Utf8 { offset }) if offset == expected
+    ));
+}
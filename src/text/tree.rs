@@ -0,0 +1,387 @@
+use crate::page::PointerKind;
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::ops::Range;
+use std::sync::atomic;
+use std::sync::atomic::AtomicU64;
+
+/// An item of a [`Tree`].
+pub(super) trait Item: Clone {
+    /// A summary of items (e.g. their size), aggregated by the nodes of a
+    /// [`Tree`].
+    type Summary: Copy + Default;
+
+    /// Returns the summary of this item.
+    fn summary(&self) -> Self::Summary;
+
+    /// Returns the summary of the items summarized by `left`, then by
+    /// `right`.
+    fn combine(left: Self::Summary, right: Self::Summary) -> Self::Summary;
+}
+
+type Link<T, P> = Option<Shared<T, P>>;
+
+/// A balanced tree of [`Item`]s.
+///
+/// Nodes aggregate the number and the [`Item::Summary`] of the items under
+/// them, so that seeking (by index or summary), splitting and splicing are
+/// `O(log n)`.
+///
+/// Nodes are shared (behind `P` pointers) between clones, and copied on write.
+pub(super) struct Tree<T: Item, P: PointerKind> {
+    root: Link<T, P>,
+}
+
+/// A node of a treap: ordered as a binary search tree by item index, and as a
+/// heap by random priority, which keeps it balanced with high probability.
+struct Node<T: Item, P: PointerKind> {
+    item:     T,
+    priority: u64,
+    /// Number of items in this subtree.
+    count:    usize,
+    /// Summary of the items in this subtree.
+    summary:  T::Summary,
+    left:     Link<T, P>,
+    right:    Link<T, P>,
+}
+
+impl<T: Item, P: PointerKind> Tree<T, P> {
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        count(&self.root)
+    }
+
+    /// Returns `true` if there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the summary of all items.
+    pub fn summary(&self) -> T::Summary {
+        summary(&self.root)
+    }
+
+    /// Returns the summary of the items before `index`, and the item at
+    /// `index`, or `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<(T::Summary, &T)> {
+        self.iter_from(index).next()
+    }
+
+    /// Returns an iterator over the items, with the summary of the items
+    /// before them.
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        self.iter_from(0)
+    }
+
+    /// Returns an iterator over the items from `index`, with the summary of
+    /// the items before them.
+    pub fn iter_from(&self, index: usize) -> Iter<'_, T, P> {
+        Iter::new(&self.root, index)
+    }
+
+    /// Returns the index of the first item whose end summary (of the items
+    /// up to it, included) does not satisfy `pred`, or `self.len()` if all
+    /// do.
+    ///
+    /// As with [`slice::partition_point`], `pred` must be `true` then `false`
+    /// along the items.
+    pub fn partition_point<F: FnMut(&T::Summary) -> bool>(&self, mut pred: F) -> usize {
+        let mut tree = self.root.as_deref();
+        let mut before = T::Summary::default();
+        let mut index = 0;
+
+        while let Some(node) = tree {
+            let start = T::combine(before, summary(&node.left));
+
+            if node.left.is_some() && !pred(&start) {
+                tree = node.left.as_deref();
+                continue;
+            }
+
+            let end = T::combine(start, node.item.summary());
+            if !pred(&end) {
+                return index + count(&node.left);
+            }
+
+            before = end;
+            index += count(&node.left) + 1;
+            tree = node.right.as_deref();
+        }
+
+        index
+    }
+
+    /// Appends `item`.
+    pub fn push(&mut self, item: T) {
+        self.root = merge(self.root.take(), Node::leaf(item));
+    }
+
+    /// Updates the item at `index` with `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, index: usize, f: F) {
+        update(&mut self.root, index, f);
+    }
+
+    /// Replaces the items in `range` with `items`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn splice<I: IntoIterator<Item = T>>(&mut self, range: Range<usize>, items: I) {
+        assert!(range.start <= range.end && range.end <= self.len());
+
+        let (before, after) = split(self.root.take(), range.end);
+        let (before, _) = split(before, range.start);
+        let items = items.into_iter().collect::<Self>();
+
+        self.root = merge(merge(before, items.root), after);
+    }
+}
+
+impl<T: Item, P: PointerKind> Clone for Tree<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<T: Item, P: PointerKind> Default for Tree<T, P> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<T: Item, P: PointerKind> FromIterator<T> for Tree<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = Self::default();
+        iter.into_iter().for_each(|item| tree.push(item));
+        tree
+    }
+}
+
+impl<T: Item + Debug, P: PointerKind> Debug for Tree<T, P> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|(_, item)| item))
+            .finish()
+    }
+}
+
+/// An iterator over a [`Tree`], with the summary of the items before each
+/// item.
+pub(super) struct Iter<'a, T: Item, P: PointerKind> {
+    /// Nodes to visit (last first), with the summary before their item.
+    stack: Vec<(T::Summary, &'a Node<T, P>)>,
+    len:   usize,
+}
+
+impl<'a, T: Item, P: PointerKind> Iter<'a, T, P> {
+    fn new(root: &'a Link<T, P>, mut index: usize) -> Self {
+        let mut iter = Self {
+            stack: Vec::new(),
+            len:   count(root).saturating_sub(index),
+        };
+        let mut tree = root.as_deref();
+        let mut before = T::Summary::default();
+
+        while let Some(node) = tree {
+            let left = count(&node.left);
+
+            if index <= left {
+                iter.stack
+                    .push((T::combine(before, summary(&node.left)), node));
+
+                if index == left {
+                    break;
+                }
+
+                tree = node.left.as_deref();
+            } else {
+                index -= left + 1;
+                before = T::combine(T::combine(before, summary(&node.left)), node.item.summary());
+                tree = node.right.as_deref();
+            }
+        }
+
+        iter
+    }
+}
+
+impl<'a, T: Item, P: PointerKind> Clone for Iter<'a, T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            stack: self.stack.clone(),
+            len:   self.len,
+        }
+    }
+}
+
+impl<'a, T: Item, P: PointerKind> Iterator for Iter<'a, T, P> {
+    type Item = (T::Summary, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (before, node) = self.stack.pop()?;
+        let start = T::combine(before, node.item.summary());
+        let mut tree = node.right.as_deref();
+
+        while let Some(node) = tree {
+            self.stack
+                .push((T::combine(start, summary(&node.left)), node));
+            tree = node.left.as_deref();
+        }
+
+        self.len -= 1;
+        Some((before, &node.item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T: Item, P: PointerKind> ExactSizeIterator for Iter<'a, T, P> {}
+
+/// A node, shared behind a `P` pointer.
+struct Shared<T: Item, P: PointerKind>(P::Pointer<Node<T, P>>);
+
+impl<T: Item, P: PointerKind> Node<T, P> {
+    /// Returns a tree of a single node, with `item`.
+    fn leaf(item: T) -> Link<T, P> {
+        Some(Shared(P::new(Self {
+            priority: priority(),
+            count: 1,
+            summary: item.summary(),
+            item,
+            left: None,
+            right: None,
+        })))
+    }
+
+    /// Updates `count` and `summary` from the item and the children.
+    fn update(&mut self) {
+        self.count = count(&self.left) + 1 + count(&self.right);
+        self.summary = T::combine(
+            T::combine(summary(&self.left), self.item.summary()),
+            summary(&self.right),
+        );
+    }
+}
+
+impl<T: Item, P: PointerKind> Clone for Node<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            item:     self.item.clone(),
+            priority: self.priority,
+            count:    self.count,
+            summary:  self.summary,
+            left:     self.left.clone(),
+            right:    self.right.clone(),
+        }
+    }
+}
+
+impl<T: Item, P: PointerKind> Shared<T, P> {
+    /// Returns the node mutably, copying it first if it is shared.
+    fn make_mut(&mut self) -> &mut Node<T, P> {
+        P::make_mut(&mut self.0)
+    }
+}
+
+impl<T: Item, P: PointerKind> Deref for Shared<T, P> {
+    type Target = Node<T, P>;
+
+    fn deref(&self) -> &Node<T, P> {
+        &self.0
+    }
+}
+
+impl<T: Item, P: PointerKind> Clone for Shared<T, P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+fn count<T: Item, P: PointerKind>(tree: &Link<T, P>) -> usize {
+    tree.as_ref().map_or(0, |node| node.count)
+}
+
+fn summary<T: Item, P: PointerKind>(tree: &Link<T, P>) -> T::Summary {
+    tree.as_ref()
+        .map_or(T::Summary::default(), |node| node.summary)
+}
+
+/// Concatenates `left` and `right`.
+fn merge<T: Item, P: PointerKind>(left: Link<T, P>, right: Link<T, P>) -> Link<T, P> {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree,
+        (Some(mut left), Some(mut right)) =>
+            if left.priority >= right.priority {
+                let node = left.make_mut();
+                node.right = merge(node.right.take(), Some(right));
+                node.update();
+                Some(left)
+            } else {
+                let node = right.make_mut();
+                node.left = merge(Some(left), node.left.take());
+                node.update();
+                Some(right)
+            },
+    }
+}
+
+/// Splits `tree` into its first `at` items and the others.
+fn split<T: Item, P: PointerKind>(tree: Link<T, P>, at: usize) -> (Link<T, P>, Link<T, P>) {
+    let mut tree = match tree {
+        Some(tree) => tree,
+        None => return (None, None),
+    };
+    let node = tree.make_mut();
+    let left = count(&node.left);
+
+    if at <= left {
+        let (before, after) = split(node.left.take(), at);
+        node.left = after;
+        node.update();
+        (before, Some(tree))
+    } else {
+        let (before, after) = split(node.right.take(), at - left - 1);
+        node.right = before;
+        node.update();
+        (Some(tree), after)
+    }
+}
+
+/// Updates the item at `index` in `tree` with `f`.
+fn update<T: Item, P: PointerKind, F: FnOnce(&mut T)>(tree: &mut Link<T, P>, index: usize, f: F) {
+    let node = tree.as_mut().expect("index in bounds").make_mut();
+    let left = count(&node.left);
+
+    match index.cmp(&left) {
+        Ordering::Less => update(&mut node.left, index, f),
+        Ordering::Equal => f(&mut node.item),
+        Ordering::Greater => update(&mut node.right, index - left - 1, f),
+    }
+
+    node.update();
+}
+
+/// Returns a pseudo-random priority (splitmix64).
+fn priority() -> u64 {
+    const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+    static STATE: AtomicU64 = AtomicU64::new(0);
+
+    let mut z = STATE
+        .fetch_add(GAMMA, atomic::Ordering::Relaxed)
+        .wrapping_add(GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,219 @@
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::page::RcKind;
+use crate::page::RcPage;
+use std::collections::VecDeque;
+use std::mem::size_of;
+use std::ops::Range;
+
+/// An edit: bytes in `range` replaced by `str`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Edit {
+    /// The replaced range, in the text before the edit.
+    pub range: Range<usize>,
+    /// The inserted text.
+    pub str:   String,
+}
+
+impl Edit {
+    /// Returns the range of the inserted text, in the text after the edit.
+    pub fn inserted(&self) -> Range<usize> {
+        self.range.start..self.range.start + self.str.len()
+    }
+}
+
+/// An undo history of [`TextIn`] (e.g. [`Text`]) snapshots.
+///
+/// Snapshots share their pages, so each entry only costs the pages which
+/// differ from the following state. The oldest entries are dropped to keep
+/// the cost under a memory budget.
+///
+/// # Usage
+///
+/// ```
+/// # use grappe::text::UndoStack;
+/// # use grappe::Text;
+/// let mut text = Text::from("Hello, world!");
+/// let mut history = UndoStack::new(1024 * 1024);
+///
+/// history.edit(&mut text, 7..12, "rust");
+/// assert!(text.to_string() == "Hello, rust!");
+///
+/// history.undo(&mut text);
+/// assert!(text.to_string() == "Hello, world!");
+///
+/// history.redo(&mut text);
+/// assert!(text.to_string() == "Hello, rust!");
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct UndoStack<P: PointerKind = RcKind> {
+    undo:   VecDeque<Entry<P>>,
+    redo:   Vec<Entry<P>>,
+    budget: usize,
+    used:   usize,
+}
+
+#[derive(Clone, Debug)]
+struct Entry<P: PointerKind> {
+    /// The text before (undo) or after (redo) the edit.
+    text:    TextIn<P>,
+    edit:    Edit,
+    cost:    usize,
+    /// The cost of the text it is swapped with, by undo or redo.
+    swapped: usize,
+}
+
+impl<P: PointerKind> UndoStack<P> {
+    /// Creates a new `UndoStack` with a memory `budget`, in bytes.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the memory budget, in bytes.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the (estimated) used memory, in bytes.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Returns `true` if there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is an edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Replaces the bytes in `range` of `text` with `str`, recording the edit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or not on char boundaries.
+    pub fn edit(&mut self, text: &mut TextIn<P>, range: Range<usize>, str: &str) {
+        let snapshot = text.snapshot();
+        text.replace_range(range.clone(), str);
+
+        self.push(snapshot, text, Edit {
+            range,
+            str: str.to_owned(),
+        });
+    }
+
+    /// Records `edit`, which turned `before` into `after`.
+    ///
+    /// Clears the redo history.
+    pub fn push(&mut self, before: TextIn<P>, after: &TextIn<P>, edit: Edit) {
+        for entry in self.redo.drain(..) {
+            self.used -= entry.cost;
+        }
+
+        let (cost, swapped) = costs(&before, after, &edit);
+        self.used += cost;
+        self.undo.push_back(Entry {
+            text: before,
+            edit,
+            cost,
+            swapped,
+        });
+
+        while self.used > self.budget {
+            match self.undo.pop_front() {
+                Some(entry) => self.used -= entry.cost,
+                None => break,
+            }
+        }
+    }
+
+    /// Undoes the last edit on `text`, returning it.
+    pub fn undo(&mut self, text: &mut TextIn<P>) -> Option<&Edit> {
+        let mut entry = self.undo.pop_back()?;
+
+        entry.swap(text, &mut self.used);
+        self.redo.push(entry);
+        self.redo.last().map(|entry| &entry.edit)
+    }
+
+    /// Redoes the last undone edit on `text`, returning it.
+    pub fn redo(&mut self, text: &mut TextIn<P>) -> Option<&Edit> {
+        let mut entry = self.redo.pop()?;
+
+        entry.swap(text, &mut self.used);
+        self.undo.push_back(entry);
+        self.undo.back().map(|entry| &entry.edit)
+    }
+
+    /// Clears the history.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.used = 0;
+    }
+}
+
+impl<P: PointerKind> Entry<P> {
+    /// Swaps the text of this entry with `text`, updating `used`.
+    fn swap(&mut self, text: &mut TextIn<P>, used: &mut usize) {
+        std::mem::swap(text, &mut self.text);
+        std::mem::swap(&mut self.cost, &mut self.swapped);
+        *used = *used - self.swapped + self.cost;
+    }
+}
+
+/// Returns the memory costs of keeping `before` next to `after`, and `after`
+/// next to `before` (with `edit`).
+///
+/// Texts share their pages but the ones rebuilt by `edit`: pages before its
+/// first rebuilt page, and after its last, are found in `O(log² pages)`.
+fn costs<P: PointerKind>(before: &TextIn<P>, after: &TextIn<P>, edit: &Edit) -> (usize, usize) {
+    let (len, other) = (before.pages.len(), after.pages.len());
+    let shared = |i: usize, j: usize| {
+        let (_, page) = before.pages.get(i).expect("index in bounds");
+        let (_, other) = after.pages.get(j).expect("index in bounds");
+        page.bytes.ptr_eq(&other.bytes)
+    };
+
+    // Pages ending before the edit, and starting after it
+    let start = before
+        .pages
+        .partition_point(|end| end.len < edit.range.start);
+    let end = before
+        .pages
+        .partition_point(|end| end.len <= edit.range.end);
+    let end = len.saturating_sub(end + 1);
+
+    let first = partition_point(start.min(other), |i| shared(i, i));
+    let last = partition_point(end.min(other - first), |i| {
+        shared(len - 1 - i, other - 1 - i)
+    });
+
+    let page = size_of::<[u8; crate::page::BYTES]>() + size_of::<RcPage>();
+    let cost = |len: usize| (len - first - last) * page + edit.str.len();
+
+    (cost(len), cost(other))
+}
+
+/// Returns the first index in `0..len` not satisfying `pred`, or `len`.
+fn partition_point<F: FnMut(usize) -> bool>(len: usize, mut pred: F) -> usize {
+    let (mut start, mut end) = (0, len);
+
+    while start < end {
+        let mid = start + (end - start) / 2;
+
+        if pred(mid) {
+            start = mid + 1;
+        } else {
+            end = mid;
+        }
+    }
+
+    start
+}
@@ -0,0 +1,394 @@
+use super::motion::LineChunks;
+use super::LineRef;
+use super::TextIn;
+use crate::page::Page;
+use crate::page::PointerKind;
+use crate::page::Record;
+use crate::utils;
+use crate::Cluster;
+use std::ops::Index;
+use std::ops::Range;
+use std::ops::RangeFull;
+
+/// Options for display widths (see [`LineRef::width`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WidthOptions {
+    /// Columns between tab stops.
+    pub tab_width:      usize,
+    /// Whether East Asian ambiguous width chars (e.g. `'°'`) are wide,
+    /// as in CJK contexts.
+    pub ambiguous_wide: bool,
+    /// Width of zero width clusters (e.g. `'\u{200B}'` or control chars), `1`
+    /// to show them.
+    pub zero_width:     usize,
+}
+
+impl WidthOptions {
+    /// Returns the display width of `cluster` at `column` (see
+    /// [`Cluster::width_with`]).
+    pub fn width(&self, cluster: &str, column: usize) -> usize {
+        Cluster::from_raw(cluster, utils::width(cluster) as u8).width_with(self, column)
+    }
+
+    /// Returns the display width of `str` at `column`.
+    pub(crate) fn str_width(&self, str: &str, column: usize) -> usize {
+        utils::clusters(str).fold(column, |column, cluster| {
+            column + self.width(cluster, column)
+        }) - column
+    }
+
+    /// Returns the [`Widths`] of `page`, `before` and `after` being the text
+    /// of its line in the previous and next pages (when continued over pages).
+    ///
+    /// A cluster split between pages is measured (as a whole) in the page
+    /// where it ends.
+    pub(super) fn page_widths<T>(&self, page: &Page<T>, before: &str, after: &str) -> Widths
+    where
+        Page<T>: Index<RangeFull, Output = [u8]>,
+    {
+        let tab = self.tab_width;
+        let mut records = page.records(0).peekable();
+        let mut widths = Widths::default();
+        let mut before = before;
+
+        while let Some(record) = records.next() {
+            let after = if records.peek().is_none() { after } else { "" };
+            let span = self.record_span(&record, before, after);
+
+            widths = widths.then(Widths::new(span, record.meta.eol.is_some(), tab));
+            before = "";
+        }
+
+        widths
+    }
+
+    /// Returns the [`Span`] of `record` (see [`WidthOptions::page_widths`]).
+    fn record_span(&self, record: &Record, before: &str, after: &str) -> Span {
+        let tab = self.tab_width;
+
+        if before.is_empty() && after.is_empty() {
+            let spaces = record.meta.spaces as usize;
+            let indent = if record.meta.tabs {
+                (0..spaces).fold(Span::default(), |span, _| span.then(Span::TAB, tab))
+            } else {
+                Span::new(spaces)
+            };
+
+            return if record.meta.ascii {
+                indent.then(Span::new(record.str.len()), tab)
+            } else {
+                utils::clusters(record.str)
+                    .fold(indent, |span, cluster| span.then(self.span(cluster), tab))
+            };
+        }
+
+        let str = format!("{before}{}{}{after}", record.meta.indent(), record.str);
+        let start = before.len();
+        let end = str.len() - after.len();
+        let mut index = 0;
+
+        utils::clusters(&str).fold(Span::default(), |span, cluster| {
+            index += cluster.len();
+
+            if start < index && index <= end {
+                span.then(self.span(cluster), tab)
+            } else {
+                span
+            }
+        })
+    }
+
+    /// Returns the [`Span`] of `cluster`.
+    fn span(&self, cluster: &str) -> Span {
+        match cluster {
+            "\t" => Span::TAB,
+            cluster => Span::new(self.width(cluster, 0)),
+        }
+    }
+}
+
+impl Default for WidthOptions {
+    fn default() -> Self {
+        Self {
+            tab_width:      8,
+            ambiguous_wide: false,
+            zero_width:     0,
+        }
+    }
+}
+
+/// Display width of a run of clusters without eol: `head` columns before the
+/// first tab, and the `tail` columns after it (if any).
+///
+/// A tab moves to a tab stop whatever the column before it, so the columns
+/// after it do not depend on where the run starts.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub(super) struct Span {
+    head: usize,
+    tail: Option<usize>,
+}
+
+impl Span {
+    const TAB: Self = Self {
+        head: 0,
+        tail: Some(0),
+    };
+
+    fn new(width: usize) -> Self {
+        Self {
+            head: width,
+            tail: None,
+        }
+    }
+
+    /// Returns the column at the end of this span, starting at `column`.
+    fn end(self, column: usize, tab: usize) -> usize {
+        match self.tail {
+            Some(tail) => stop(column + self.head, tab) + tail,
+            None => column + self.head,
+        }
+    }
+
+    /// Returns this span followed by `other`.
+    fn then(self, other: Self, tab: usize) -> Self {
+        match self.tail {
+            Some(tail) => Self {
+                head: self.head,
+                tail: Some(other.end(tail, tab)),
+            },
+            None => Self {
+                head: self.head + other.head,
+                tail: other.tail,
+            },
+        }
+    }
+}
+
+/// Returns the tab stop following `column`.
+fn stop(column: usize, tab: usize) -> usize {
+    column + tab - column % tab.max(1)
+}
+
+/// Display widths of a run of records: the `first` line (up to the first eol,
+/// if `ended`), the widest line after it (`max`) and the `last` line (after
+/// the last eol, if `ended`).
+///
+/// Runs concatenate (see [`Widths::then`]), so that [`Pages`] aggregates the
+/// widths of their pages.
+///
+/// [`Pages`]: super::Pages
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub(super) struct Widths {
+    /// Tab width the run is measured with (`0` if empty).
+    tab:   usize,
+    first: Span,
+    ended: bool,
+    max:   usize,
+    last:  Span,
+}
+
+impl Widths {
+    fn new(span: Span, ended: bool, tab: usize) -> Self {
+        Self {
+            tab,
+            first: span,
+            ended,
+            ..Default::default()
+        }
+    }
+
+    /// Returns this run followed by `other`.
+    pub(super) fn then(self, other: Self) -> Self {
+        let tab = self.tab.max(other.tab);
+
+        match (self.ended, other.ended) {
+            (false, _) => Self {
+                tab,
+                first: self.first.then(other.first, tab),
+                ..other
+            },
+            (true, false) => Self {
+                tab,
+                last: self.last.then(other.first, tab),
+                ..self
+            },
+            (true, true) => Self {
+                tab,
+                first: self.first,
+                ended: true,
+                max: self
+                    .max
+                    .max(self.last.then(other.first, tab).end(0, tab))
+                    .max(other.max),
+                last: other.last,
+            },
+        }
+    }
+
+    /// Returns the display width of the widest line, for a run starting a
+    /// line.
+    pub(super) fn max(&self) -> usize {
+        let first = self.first.end(0, self.tab);
+
+        if self.ended {
+            first.max(self.max).max(self.last.end(0, self.tab))
+        } else {
+            first
+        }
+    }
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Returns the display width of the widest line (with the text's
+    /// [`WidthOptions`]), e.g. for horizontal scrollbars.
+    ///
+    /// Widths are aggregated per page, lines continued over several pages
+    /// being measured as a whole. Pages are measured on the first call after
+    /// loading or editing them, then this is `O(1)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::Text;
+    /// # use grappe::text::WidthOptions;
+    /// let line = "猫\t".repeat(100);
+    /// let mut text = Text::from(format!("fn main() {{\n{line}\n}}").as_str());
+    ///
+    /// assert!(text.max_width() == 100 * 8);
+    ///
+    /// text.set_width_options(WidthOptions {
+    ///     tab_width: 4,
+    ///     ..Default::default()
+    /// });
+    /// assert!(text.max_width() == 100 * 4);
+    /// ```
+    pub fn max_width(&mut self) -> usize {
+        self.pages.max_width()
+    }
+
+    /// Returns the display width options.
+    pub fn width_options(&self) -> WidthOptions {
+        self.pages.width_options()
+    }
+
+    /// Sets the display width options, to measure all pages again if they
+    /// changed.
+    pub fn set_width_options(&mut self, options: WidthOptions) {
+        self.pages.set_width_options(options);
+    }
+}
+
+impl<'a, P: PointerKind> LineRef<'a, P> {
+    /// Returns the display width of this line (with the text's
+    /// [`WidthOptions`]), leading spaces (or tabs) included, eol excluded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::Text;
+    /// let text = Text::from("\tlet 猫 = \"🐈\";");
+    /// let line = text.line(0).unwrap();
+    ///
+    /// assert!(line.width() == 22);
+    /// assert!(line.byte_to_column(5) == Some(12)); // '猫'
+    /// assert!(line.column_to_byte(13) == 5);
+    /// ```
+    pub fn width(&self) -> usize {
+        if self.ascii {
+            return self.indent_width(self.indent().len()) + self.len();
+        }
+
+        self.columns()
+            .last()
+            .map_or(0, |(_, column, width, _)| column + width)
+    }
+
+    /// Returns the display column of the cluster at byte `byte` (in this
+    /// line), or `None` if past the end (eol excluded) or not on a char
+    /// boundary.
+    pub fn byte_to_column(&self, byte: usize) -> Option<usize> {
+        let end = self.indent().len() + self.len();
+
+        if byte > end {
+            return None;
+        }
+
+        // Printable ASCII: one column per byte
+        if self.ascii {
+            let indent = self.indent().len();
+            return Some(self.indent_width(byte.min(indent)) + byte.saturating_sub(indent));
+        }
+
+        if !LineChunks::new(self).is_char_boundary(byte) {
+            return None;
+        }
+
+        let mut width = 0;
+        for (range, column, cluster_width, _) in self.columns() {
+            if byte < range.end {
+                return Some(column);
+            }
+
+            width = column + cluster_width;
+        }
+
+        Some(width)
+    }
+
+    /// Returns the byte (in this line) of the cluster at display column
+    /// `column`, or of the end of the line (before eol) if past it.
+    pub fn column_to_byte(&self, column: usize) -> usize {
+        if self.ascii {
+            let indent = self.indent().len();
+            let indent_width = self.indent_width(indent);
+
+            return if column >= indent_width {
+                (indent + column - indent_width).min(indent + self.len())
+            } else if self.tabs() != 0 {
+                column / self.text.width_options().tab_width
+            } else {
+                column
+            };
+        }
+
+        self.columns()
+            .find(|(_, start, width, _)| column < start + width)
+            .map_or(self.indent().len() + self.len(), |(range, ..)| range.start)
+    }
+
+    /// Returns the display width of the first `spaces` leading spaces (or
+    /// tabs).
+    fn indent_width(&self, spaces: usize) -> usize {
+        if self.tabs() != 0 {
+            spaces * self.text.width_options().tab_width
+        } else {
+            spaces
+        }
+    }
+
+    /// Returns an iterator over the clusters (eol excluded) as their byte
+    /// range, column, width and first char.
+    pub(super) fn columns(&self) -> impl 'a + Iterator<Item = (Range<usize>, usize, usize, char)> {
+        let options = self.text.width_options();
+        let chunks = LineChunks::new(self);
+        let end = self.indent().len() + self.len();
+        let mut start = 0;
+        let mut column = 0;
+
+        std::iter::from_fn(move || {
+            if start == end {
+                return None;
+            }
+
+            let range = start..chunks.next_boundary(start).min(end);
+            let cluster = chunks.str(range.clone());
+            let width = options.width(&cluster, column);
+            let char = cluster.chars().next().expect("non empty cluster");
+            let item = (range.clone(), column, width, char);
+
+            start = range.end;
+            column += width;
+            Some(item)
+        })
+    }
+}
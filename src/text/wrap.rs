@@ -0,0 +1,276 @@
+use super::tree;
+use super::tree::Tree;
+use super::LineRef;
+use super::Text;
+use super::TextIn;
+use crate::page::ArcKind;
+use crate::page::PointerKind;
+use std::ops::Range;
+
+/// Options for [`SoftWrap`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WrapOptions {
+    /// Width of the viewport, in columns (`0` to not wrap).
+    pub width:  usize,
+    /// Whether to wrap after whitespace when possible, rather than at any
+    /// grapheme cluster.
+    pub words:  bool,
+    /// Whether to indent continuation rows as their line, when the
+    /// indentation is at most half the width.
+    pub indent: bool,
+}
+
+impl Default for WrapOptions {
+    fn default() -> Self {
+        Self {
+            width:  80,
+            words:  true,
+            indent: true,
+        }
+    }
+}
+
+/// A visual row of a [`SoftWrap`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Row {
+    /// Line of the row.
+    pub line:   usize,
+    /// Byte range (in the line) of the row, eol excluded.
+    pub range:  Range<usize>,
+    /// Indentation (in columns) of the row, before its content.
+    pub indent: usize,
+}
+
+/// Soft-wraps the lines of a [`Text`] in visual rows.
+///
+/// Lines are split at grapheme clusters (or after whitespace, see
+/// [`WrapOptions::words`]) so that rows fit the width, unless a single
+/// cluster does not. Clusters keep the width they have in their line (see
+/// [`LineRef::width`]), tabs included.
+///
+/// Layouts are per line: after an edit, only the edited lines need to be laid
+/// out again, with [`Self::splice`].
+///
+/// # Usage
+///
+/// ```
+/// # use grappe::text::SoftWrap;
+/// # use grappe::text::WrapOptions;
+/// # use grappe::Text;
+/// let text = Text::from("fn main() { let x = 1; }\n    foo bar baz");
+/// let options = WrapOptions {
+///     width: 12,
+///     ..Default::default()
+/// };
+/// let wrap = SoftWrap::new(&text, options);
+///
+/// assert!(wrap.rows() == 4);
+/// assert!(wrap.row(1).unwrap().range == (12..24)); // "let x = 1; }"
+/// assert!(wrap.row(3).unwrap().indent == 4); // "baz"
+///
+/// assert!(wrap.to_visual(&text, 1, 13) == Some((3, 5))); // "a" of "baz"
+/// assert!(wrap.from_visual(&text, 3, 5) == Some((1, 13)));
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct SoftWrap {
+    options: WrapOptions,
+    /// Rows of each line, aggregating their number (in `Arc`s, to be
+    /// `Send`).
+    lines:   Tree<Rows, ArcKind>,
+}
+
+/// The rows of a line.
+#[derive(Clone, Default, Debug)]
+struct Rows {
+    /// Byte index and column (in the line) of each row but the first.
+    starts: Vec<(usize, usize)>,
+    /// Indentation of continuation rows.
+    indent: usize,
+    /// Byte index of the end of the line (eol excluded).
+    end:    usize,
+}
+
+impl tree::Item for Rows {
+    /// Number of rows.
+    type Summary = usize;
+
+    fn summary(&self) -> usize {
+        self.starts.len() + 1
+    }
+
+    fn combine(left: usize, right: usize) -> usize {
+        left + right
+    }
+}
+
+impl SoftWrap {
+    /// Lays out `text` with `options`.
+    pub fn new<P: PointerKind>(text: &TextIn<P>, options: WrapOptions) -> Self {
+        let mut wrap = Self {
+            options,
+            ..Default::default()
+        };
+
+        wrap.splice(text, 0..0, text.lines + 1);
+        wrap
+    }
+
+    /// Returns the options.
+    pub fn options(&self) -> WrapOptions {
+        self.options
+    }
+
+    /// Lays out `text` again with `options`.
+    pub fn set_options<P: PointerKind>(&mut self, text: &TextIn<P>, options: WrapOptions) {
+        *self = Self::new(text, options);
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.lines.summary()
+    }
+
+    /// Returns the rows of line `line`, or `None` if out of bounds.
+    pub fn line_rows(&self, line: usize) -> Option<Range<usize>> {
+        let (start, rows) = self.lines.get(line)?;
+        Some(start..start + rows.starts.len() + 1)
+    }
+
+    /// Returns row `row`, or `None` if out of bounds.
+    pub fn row(&self, row: usize) -> Option<Row> {
+        let line = self.lines.partition_point(|&end| end <= row);
+        let (first, rows) = self.lines.get(line)?;
+        let i = row - first;
+
+        let start = i.checked_sub(1).map_or(0, |i| rows.starts[i].0);
+        let end = rows.starts.get(i).map_or(rows.end, |&(end, _)| end);
+
+        Some(Row {
+            line,
+            range: start..end,
+            indent: if i == 0 { 0 } else { rows.indent },
+        })
+    }
+
+    /// Returns the row and column of byte `byte` in line `line`, or `None`
+    /// if out of bounds (eol excluded) or not on a char boundary.
+    ///
+    /// `text` must be the text laid out.
+    pub fn to_visual<P: PointerKind>(
+        &self,
+        text: &TextIn<P>,
+        line: usize,
+        byte: usize,
+    ) -> Option<(usize, usize)> {
+        let (first, rows) = self.lines.get(line)?;
+        let column = text.line(line)?.byte_to_column(byte)?;
+        let i = rows.starts.partition_point(|&(start, _)| start <= byte);
+
+        let column = match i.checked_sub(1) {
+            Some(i) => rows.indent + column - rows.starts[i].1,
+            None => column,
+        };
+
+        Some((first + i, column))
+    }
+
+    /// Returns the line and byte (in the line) at column `column` of row
+    /// `row` (or of the last cluster of the row, if past its end), or `None`
+    /// if out of bounds.
+    ///
+    /// `text` must be the text laid out.
+    pub fn from_visual<P: PointerKind>(
+        &self,
+        text: &TextIn<P>,
+        row: usize,
+        column: usize,
+    ) -> Option<(usize, usize)> {
+        let Row {
+            line,
+            range,
+            indent,
+        } = self.row(row)?;
+        let (first, rows) = self.lines.get(line)?;
+        let i = row - first;
+        let start = i.checked_sub(1).map_or(0, |i| rows.starts[i].1);
+        let line_ref = text.line(line)?;
+
+        let byte = line_ref
+            .column_to_byte(start + column.saturating_sub(indent))
+            .max(range.start);
+
+        if byte < range.end || i == rows.starts.len() {
+            Some((line, byte))
+        } else {
+            let end = text.prev_grapheme(line_ref.offset() + range.end)?;
+            Some((line, (end - line_ref.offset()).max(range.start)))
+        }
+    }
+
+    /// Lays out lines again after an edit which replaced lines `old` (of the
+    /// text previously laid out) with `len` lines (of `text`, from
+    /// `old.start`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `old` is out of bounds, or if lines are missing in `text`.
+    pub fn splice<P: PointerKind>(&mut self, text: &TextIn<P>, old: Range<usize>, len: usize) {
+        let first = old.start;
+        let lines = text
+            .lines_range(first..first + len)
+            .expect("lines in bounds");
+        let options = self.options;
+
+        self.lines
+            .splice(old, lines.map(|line| layout(&line, &options)));
+        debug_assert!(self.lines.len() == text.lines + 1);
+    }
+}
+
+/// Returns the rows of `line`.
+fn layout<P: PointerKind>(line: &LineRef<P>, options: &WrapOptions) -> Rows {
+    let mut rows = Rows {
+        end: line.indent().len() + line.len(),
+        ..Default::default()
+    };
+    let width = options.width;
+
+    if width == 0 {
+        return rows;
+    }
+
+    if options.indent {
+        let indent = line
+            .columns()
+            .find(|&(.., char)| char != ' ' && char != '\t')
+            .map_or(0, |(_, column, ..)| column);
+
+        rows.indent = if indent <= width / 2 { indent } else { 0 };
+    }
+
+    let mut start = 0;
+    let mut x = 0;
+    // Byte index and column after the last whitespace of the row
+    let mut after_space = None;
+
+    for (range, column, cluster_width, char) in line.columns() {
+        while x + cluster_width > width && range.start > start {
+            let (byte, byte_column) = match after_space.take() {
+                Some(after_space) => after_space,
+                None => (range.start, column),
+            };
+
+            rows.starts.push((byte, byte_column));
+            start = byte;
+            x = rows.indent + column - byte_column;
+        }
+
+        x += cluster_width;
+
+        if options.words && char.is_whitespace() {
+            after_space = Some((range.end, column + cluster_width));
+        }
+    }
+
+    rows
+}
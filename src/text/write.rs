@@ -0,0 +1,125 @@
+use super::Text;
+use super::TextIn;
+use crate::page::PointerKind;
+use crate::Eol;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+
+/// Options for writing a [`Text`] (see [`Text::write_to_with`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct WriteOptions {
+    /// Eol replacing the eols of all lines, `None` to keep them as is.
+    pub eol:       Option<Eol>,
+    /// Whether to strip trailing whitespace from lines.
+    pub trim:      bool,
+    /// What to do with the eol ending the text.
+    pub final_eol: FinalEol,
+}
+
+/// What to do with the eol ending a [`Text`] (see [`WriteOptions`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum FinalEol {
+    /// Keep the text as is.
+    #[default]
+    Keep,
+    /// End the text with an eol, unless empty (the options' eol, or the
+    /// dominant eol of the text, or `'\n'`).
+    Ensure,
+    /// Strip the eol ending the text, if any (after trimming trailing
+    /// whitespace, if [`WriteOptions::trim`]).
+    Strip,
+}
+
+impl<P: PointerKind> TextIn<P> {
+    /// Writes this text to `writer`.
+    ///
+    /// See [`Self::write_to_with`].
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.write_to_with(writer, WriteOptions::default())
+    }
+
+    /// Writes this text to `writer`, with `options`.
+    ///
+    /// Chunks are streamed from the pages (through a buffer), without
+    /// materializing a `String`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use grappe::text::WriteOptions;
+    /// # use grappe::Eol;
+    /// # use grappe::Text;
+    /// let text = Text::from("fn main() {  \r\n    \r\n}\n");
+    /// let options = WriteOptions {
+    ///     eol: Some(Eol::LF),
+    ///     trim: true,
+    ///     ..Default::default()
+    /// };
+    /// let mut bytes = Vec::new();
+    ///
+    /// text.write_to_with(&mut bytes, options).unwrap();
+    /// assert!(bytes == b"fn main() {\n\n}\n");
+    /// ```
+    pub fn write_to_with<W: Write>(&self, writer: &mut W, options: WriteOptions) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        // Whitespace since the last non whitespace char of the line
+        let mut whitespace = String::new();
+        // Whether the line is empty so far (once trimmed)
+        let mut empty = true;
+        // The last eol, written before what follows it (if anything, once
+        // trimmed), so that the final eol policy applies to the written text
+        let mut pending = None::<Eol>;
+
+        for (_, page) in &self.pages {
+            for record in page.records(0) {
+                let spaces = (record.meta.spaces != 0).then(|| record.meta.indent());
+
+                for str in spaces.into_iter().chain(Some(record.str)) {
+                    let (write, keep) = if options.trim {
+                        let end = str.trim_end().len();
+                        (&str[..end], &str[end..])
+                    } else {
+                        (str, "")
+                    };
+
+                    if !write.is_empty() {
+                        if let Some(eol) = pending.take() {
+                            writer.write_all(eol.as_str().as_bytes())?;
+                        }
+                        writer.write_all(whitespace.as_bytes())?;
+                        writer.write_all(write.as_bytes())?;
+                        whitespace.clear();
+                        empty = false;
+                    }
+                    whitespace.push_str(keep);
+                }
+
+                if let Some(eol) = record.meta.eol {
+                    if let Some(eol) = pending.replace(options.eol.unwrap_or(eol)) {
+                        writer.write_all(eol.as_str().as_bytes())?;
+                    }
+                    whitespace.clear();
+                    empty = true;
+                }
+            }
+        }
+
+        if let Some(eol) = pending {
+            if options.final_eol != FinalEol::Strip {
+                writer.write_all(eol.as_str().as_bytes())?;
+            }
+        }
+
+        if options.final_eol == FinalEol::Ensure && !empty {
+            let eol = options
+                .eol
+                .or_else(|| self.dominant_eol())
+                .unwrap_or(Eol::LF);
+
+            writer.write_all(eol.as_str().as_bytes())?;
+        }
+
+        writer.flush()
+    }
+}
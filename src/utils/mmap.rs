@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+
+/// A read-only, private memory map of a whole file.
+///
+/// Falls back to reading the file on non-unix platforms.
+#[derive(Debug)]
+pub struct Mmap {
+    #[cfg(unix)]
+    ptr:   *const u8,
+    #[cfg(unix)]
+    len:   usize,
+    #[cfg(not(unix))]
+    bytes: Vec<u8>,
+}
+
+impl Mmap {
+    /// Maps `file`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified (e.g. truncated) while mapped, by this
+    /// process or others: the bytes would change under shared references, or
+    /// accesses fault.
+    #[cfg(unix)]
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "file too large to map"))?;
+
+        // Empty maps are invalid
+        if len == 0 {
+            return Ok(Self {
+                ptr: std::ptr::NonNull::dangling().as_ptr(),
+                len,
+            });
+        }
+
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ,
+            libc::MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        );
+
+        if ptr == libc::MAP_FAILED {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Self {
+                ptr: ptr as *const u8,
+                len,
+            })
+        }
+    }
+
+    /// Maps `file`.
+    ///
+    /// # Safety
+    ///
+    /// Safe on non-unix platforms, where the file is read.
+    #[cfg(not(unix))]
+    pub unsafe fn map(mut file: &File) -> io::Result<Self> {
+        use std::io::Read;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        Ok(Self { bytes })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    #[cfg(unix)]
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    #[cfg(not(unix))]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+        }
+    }
+}
+
+// The map is read-only
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
@@ -2,10 +2,14 @@
 mod macros;
 
 mod leading;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod stack_vec;
 mod validator;
 
 pub use leading::*;
+#[cfg(feature = "mmap")]
+pub use mmap::*;
 pub use stack_vec::*;
 pub use validator::*;
 
@@ -32,6 +36,17 @@ pub fn cwidth(char: char) -> u8 {
     unicode_width::UnicodeWidthChar::width(char).unwrap_or(0) as u8
 }
 
+/// Returns the display width of a string slice, East Asian ambiguous width
+/// chars being wide.
+pub fn width_cjk(str: &str) -> usize {
+    unicode_width::UnicodeWidthStr::width_cjk(str)
+}
+
+/// Returns `true` if `byte` continues a UTF-8 char.
+pub fn is_continuation(byte: u8) -> bool {
+    (0x80..0xC0).contains(&byte)
+}
+
 /// Returns an iterator over the extended grapheme clusters of a string
 /// slice.
 pub fn clusters(str: &str) -> impl DoubleEndedIterator<Item = &str> {
@@ -96,8 +111,8 @@ pub fn to_range<T: RangeBounds<usize>>(range: T, len: usize) -> Option<Range<usi
         return None;
     }
 
-    let start = to_start(range.end_bound());
-    if end > start {
+    let start = to_start(range.start_bound());
+    if start > end {
         return None;
     }
 
@@ -108,7 +123,7 @@ pub fn to_range<T: RangeBounds<usize>>(range: T, len: usize) -> Option<Range<usi
 /// without checking if `start <= end`.
 pub fn to_range_unchecked<T: RangeBounds<usize>>(range: T, len: usize) -> Range<usize> {
     let end = to_end(range.end_bound(), len);
-    let start = to_start(range.end_bound());
+    let start = to_start(range.start_bound());
 
     debug_assert!(start <= end);
     debug_assert!(end <= len);
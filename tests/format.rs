@@ -0,0 +1,54 @@
+//! The on-page format at the real page size (unit tests use 10 bytes pages).
+
+use grappe::page::*;
+use grappe::Text;
+
+#[test]
+fn format() {
+    let long = format!("  {}\n", "é🦀\t".repeat(BYTES));
+    let strs = ["", "a", "\t\tfoo\n  bar\r\n\n\u{2028}", " \u{301}\n", &long];
+
+    assert!(BYTES == 1024 - 2 * std::mem::size_of::<usize>());
+
+    for str in strs {
+        let text = Text::from(str);
+        let mut string = String::new();
+
+        for (_, page) in &text.pages {
+            let bytes = page.to_bytes();
+            let copy = Page::from_bytes(&bytes).unwrap();
+            let borrowed = PageRef::view(&bytes).unwrap();
+
+            assert!(copy.size() == page.size());
+            assert!(borrowed.size() == page.size());
+            assert!(copy.to_bytes() == bytes);
+            assert!(borrowed.chunks().eq(page.chunks()));
+            string.extend(borrowed.chunks());
+        }
+
+        assert!(string == str);
+    }
+
+    // A line longer than a page is continued
+    assert!(Text::from(long.as_str()).pages.len() > 1);
+}
+
+#[test]
+fn golden() {
+    let text = Text::from("\t\tab\n  é\r\n\n");
+    let (_, page) = text.pages.iter().next().unwrap();
+    let bytes = page.to_bytes();
+    let [size0, size1] = (BYTES as u16).to_le_bytes();
+
+    assert!(text.pages.len() == 1);
+    assert!(bytes[..HEADER] == [4, 0, size0, size1, 14, 0, 12, 0, 11, 0, 11, 0, 3, 0, 0, 0]);
+    assert!(
+        bytes[HEADER..][..14]
+            == [
+                8, 4, 0, 66, 97, 98, // LF, len 2, ascii, 2 tabs, "ab"
+                64, 6, 1, 1, 32, 195, 169, // CRLF, len 3, 2 chars, 1 space, " é"
+                136, // LF
+            ]
+    );
+    assert!(bytes[HEADER + 14..].iter().all(|&byte| byte == 0));
+}